@@ -0,0 +1,33 @@
+use std::{env, path::PathBuf};
+
+use anyhow::{bail, Result};
+use image::GenericImageView;
+use voxel_benchmark::packs::atlas::AtlasBuilder;
+
+/// Packs every PNG of a directory into an atlas image and a TexturePacker XML
+///
+/// Usage: `atlas-builder <tile directory> <atlas.xml>`, the image is written
+/// next to the XML with a `.png` extension.
+fn main() -> Result<()> {
+    env_logger::Builder::from_default_env().init();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (tiles, xml) = match args.as_slice() {
+        [tiles, xml] => (tiles, PathBuf::from(xml)),
+        _ => bail!("usage: atlas-builder <tile directory> <atlas.xml>"),
+    };
+    let builder = AtlasBuilder::from_dir(tiles)?;
+    if builder.is_empty() {
+        bail!("no PNG files in {}", tiles);
+    }
+    let atlas = builder.build();
+    let image = xml.with_extension("png");
+    atlas.save(&xml, &image)?;
+    println!(
+        "packed {} tiles into {}x{} {}",
+        builder.len(),
+        atlas.image.width(),
+        atlas.image.height(),
+        image.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use rand::{rngs::StdRng, SeedableRng};
+use voxel_benchmark::{
+    generator::flat::Flat,
+    mesher::{culling::Culling, greedy::Greedy, Mesher},
+    packs::{basic::*, Pack, SimpleBlocks},
+    *,
+};
+
+const WIDTH: usize = 4;
+const LENGTH: usize = 4;
+const CHUNK_SIZE: usize = 8192;
+const CHUNK_WIDTH: usize = 16;
+const ROUNDS: usize = 32;
+
+type BenchWorld = DynamicWorld<BasicId, CHUNK_SIZE, CHUNK_WIDTH>;
+
+fn mesh_chunks(
+    world: &BenchWorld,
+    mesher: &dyn Mesher<BasicId, CHUNK_SIZE, CHUNK_WIDTH, BenchWorld>,
+    chunks: impl Iterator<Item = ChunkCoord>,
+) -> usize {
+    chunks
+        .map(|coord| mesher.mesh(world, BasicPack::get_map(), coord).face_count())
+        .sum()
+}
+
+/// Apply `edits` random edits per round, then remesh either every chunk or only
+/// the dirty ones
+fn bench(
+    name: &str,
+    mesher: &dyn Mesher<BasicId, CHUNK_SIZE, CHUNK_WIDTH, BenchWorld>,
+    edits: usize,
+) {
+    let mut world = BenchWorld::new(WIDTH, LENGTH);
+    world.generate(&Flat::new_simple(
+        CHUNK_SIZE / CHUNK_WIDTH / CHUNK_WIDTH / 2,
+    ));
+    world.take_dirty();
+    let blocks = SimpleBlocks::of();
+    // The same edits for every mesher
+    let mut rng = StdRng::seed_from_u64(edits as u64);
+    let (mut full, mut incremental, mut remeshed) = (Duration::ZERO, Duration::ZERO, 0);
+    for _ in 0..ROUNDS {
+        random_edits(&mut world, edits, &blocks, &mut rng);
+        let dirty = world.take_dirty();
+        remeshed += dirty.len();
+        let start = Instant::now();
+        mesh_chunks(&world, mesher, dirty.into_iter());
+        incremental += start.elapsed();
+        let start = Instant::now();
+        mesh_chunks(&world, mesher, world.into_iter().map(|(coord, _)| coord));
+        full += start.elapsed();
+    }
+    println!(
+        "{:<8} {:>5} edits full {:>10.2?} incremental {:>10.2?} ({:.1} chunks/round)",
+        name,
+        edits,
+        full / ROUNDS as u32,
+        incremental / ROUNDS as u32,
+        remeshed as f32 / ROUNDS as f32
+    );
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_default_env().init();
+    for &edits in &[1, 4, 16, 64] {
+        bench("culling", &Culling, edits);
+        bench("greedy", &Greedy, edits);
+    }
+    Ok(())
+}
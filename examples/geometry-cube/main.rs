@@ -6,37 +6,43 @@ use glium::{
     DrawParameters, Program, Surface, VertexBuffer,
 };
 use std::marker::PhantomData;
-use voxel_benchmark::*;
+use strum::IntoEnumIterator;
+use voxel_benchmark::{
+    mesher::culling::{face_texture, is_face_visible},
+    *,
+};
 
 #[derive(Copy, Clone)]
 struct PointInfo {
     position: [f32; 3],
     comp_info: [u32; 3],
+    face_mask: u32,
 }
 
-implement_vertex!(PointInfo, position, comp_info);
+implement_vertex!(PointInfo, position, comp_info, face_mask);
 
-fn gen_cube_point<
-    Id: BlockId,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
->(
+fn gen_cube_point<const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
     vertex: &mut WriteMapping<[PointInfo]>,
-    chunk_pos: ChunkPos<SIZE, WIDTH>,
+    coord: ChunkCoord,
     block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
-    definitions: &EnumMap<Id, SolidBlockDefinition>,
-    id: Id,
+    faces: &EnumMap<BlockFace, TextureIndex>,
+    face_mask: u32,
     current: u32,
 ) {
-    let comp_info = unsafe { std::mem::transmute(definitions[id]) };
-    let origin = WorldPosition::from((chunk_pos, block_pos));
+    // Two texture indices per component, in `BlockFace` order
+    let index = |face| faces[face].0 as u32;
+    let comp_info = [
+        index(BlockFace::North) | index(BlockFace::South) << 16,
+        index(BlockFace::East) | index(BlockFace::West) << 16,
+        index(BlockFace::Up) | index(BlockFace::Down) << 16,
+    ];
+    let origin = WorldPosition::from((coord, block_pos));
     vertex.set(
         current as usize,
         PointInfo {
             position: origin.into(),
             comp_info,
+            face_mask,
         },
     );
 }
@@ -52,29 +58,18 @@ impl BufferGroup {
     }
 }
 
-struct GeometryCubeRenderer<
-    Id: BlockId,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
-> {
-    phat: PhantomData<WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>,
+struct GeometryCubeRenderer<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize> {
+    phat: PhantomData<WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>>,
     program: Program,
     buffers: Vec<BufferGroup>,
 }
 
-impl<
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    > GeometryCubeRenderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    GeometryCubeRenderer<Id, CHUNK_SIZE, CHUNK_WIDTH>
 {
-    fn new<F: Facade>(facade: &F) -> Self {
-        let mut buffers = Vec::with_capacity(SIZE);
-        for _ in 0..SIZE {
+    fn new<F: Facade>(facade: &F, chunks: usize) -> Self {
+        let mut buffers = Vec::with_capacity(chunks);
+        for _ in 0..chunks {
             buffers.push(BufferGroup::new::<F, CHUNK_SIZE>(facade).unwrap());
         }
         Self {
@@ -83,42 +78,71 @@ impl<
             buffers,
         }
     }
+
+    fn prepare_chunk(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>, coord: ChunkCoord) {
+        let world = &info.world;
+        let group = &mut self.buffers[world.index_of(coord).unwrap()];
+        let mut writevertex = group.vertex.map_write();
+        group.count = 0;
+        for (block_pos, block) in world[coord].iter_blocks() {
+            let (id, state) = match block {
+                Block::Solid { id, state } => (id, state),
+                Block::Empty => continue,
+            };
+            // The geometry shader only knows how to expand cubes
+            if !info.definitions[id].kind().is_cube() {
+                continue;
+            }
+            let face_mask = BlockFace::iter()
+                .filter(|&face| is_face_visible(world, info.definitions, coord, block_pos, face))
+                .fold(0, |mask, face| mask | (1 << face as u32));
+            if face_mask == 0 {
+                continue;
+            }
+            // Variants and connected textures apply, but the geometry shader
+            // cannot turn textures
+            let faces = EnumMap::from(|face| {
+                face_texture(
+                    world,
+                    &info.definitions[id],
+                    coord,
+                    block_pos,
+                    (id, state),
+                    face,
+                )
+                .0
+            });
+            gen_cube_point(
+                &mut writevertex,
+                coord,
+                block_pos,
+                &faces,
+                face_mask,
+                group.count,
+            );
+            group.count += 1;
+        }
+    }
 }
 
 struct GeometryCube;
 
-impl<
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    > Renderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
-    for GeometryCubeRenderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH> for GeometryCubeRenderer<Id, CHUNK_SIZE, CHUNK_WIDTH>
 {
-    fn prepare(&mut self, info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>) {
-        for ((chunk_pos, chunk), group) in info.world.as_ref().into_iter().zip(&mut self.buffers) {
-            let mut writevertex = group.vertex.map_write();
-            group.count = 0;
-            for (block_pos, id) in chunk.iter_solid() {
-                gen_cube_point(
-                    &mut writevertex,
-                    chunk_pos,
-                    block_pos,
-                    info.definitions,
-                    id,
-                    group.count,
-                );
-                group.count += 1;
-            }
+    fn prepare(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>) {
+        for (coord, _) in &info.world {
+            self.prepare_chunk(info, coord);
         }
     }
 
-    fn render(
-        &self,
-        mut frame: glium::Frame,
-        info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>,
-    ) {
+    fn update(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>, dirty: &[ChunkCoord]) {
+        for &coord in dirty {
+            self.prepare_chunk(info, coord);
+        }
+    }
+
+    fn render(&self, mut frame: glium::Frame, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>) {
         frame.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
         let aspect_ratio = {
             let dim = frame.get_dimensions();
@@ -133,10 +157,17 @@ impl<
             .minify_filter(glium::uniforms::MinifySamplerFilter::LinearMipmapLinear)
             .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
             .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp);
+        let animations = info
+            .animations
+            .sampled()
+            .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest);
         let uniforms = uniform! {
             tile: sampled,
             perspective : perspective.to_cols_array_2d(),
             view_model: view_model,
+            animations: animations,
+            time: info.time_millis(),
         };
         for group in &self.buffers {
             let count = group.count as usize;
@@ -166,18 +197,14 @@ impl<
 }
 
 impl RendererProvider for GeometryCube {
-    fn get_renderer<
-        F: Facade,
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    >(
+    fn get_renderer<F: Facade, Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
         facade: &F,
-        _info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>,
-    ) -> Result<Box<dyn Renderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>> {
-        Ok(Box::new(GeometryCubeRenderer::new(facade)))
+        info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    ) -> Result<Box<dyn Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH>>> {
+        Ok(Box::new(GeometryCubeRenderer::new(
+            facade,
+            info.world.len(),
+        )))
     }
 }
 
@@ -2,10 +2,13 @@
 
 layout(location = 0) in vec3 position;
 layout(location = 1) in uvec3 comp_info;
+layout(location = 2) in uint face_mask;
 
 layout(location = 0) out uvec3 gcomp;
+layout(location = 1) out uint gmask;
 
 void main() {
   gcomp = comp_info;
+  gmask = face_mask;
   gl_Position = vec4(position, 0.0);
 }
\ No newline at end of file
@@ -1,13 +1,14 @@
 use anyhow::Result;
-use enum_map::EnumMap;
 use glam::f32 as math;
 use glium::{
     backend::Facade, buffer::WriteMapping, implement_vertex, uniform, BackfaceCullingMode, Depth,
     DrawParameters, Program, Surface, VertexBuffer,
 };
 use std::marker::PhantomData;
-use strum::IntoEnumIterator;
-use voxel_benchmark::*;
+use voxel_benchmark::{
+    mesher::{culling::visible_faces, Face, FaceShape},
+    *,
+};
 
 #[derive(Copy, Clone)]
 struct PosTex {
@@ -17,29 +18,15 @@ struct PosTex {
 
 implement_vertex!(PosTex, position, tex_info);
 
-fn gen_cube_mesh<
-    Id: BlockId,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
->(
-    vertex: &mut WriteMapping<[PosTex]>,
-    chunk_pos: ChunkPos<SIZE, WIDTH>,
-    block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
-    definitions: &EnumMap<Id, SolidBlockDefinition>,
-    id: Id,
-    current: u32,
-    face: BlockFace,
-) {
-    let faceid = face as u8;
-    let origin = WorldPosition::from((chunk_pos, block_pos));
-    let def = definitions[id][face];
+fn gen_cube_mesh(vertex: &mut WriteMapping<[PosTex]>, face: &Face, current: u32) {
+    let faceid = face.face as u8;
     vertex.set(
         current as usize,
         PosTex {
-            position: origin.into(),
-            tex_info: ((def.0 as u32) << 16u32) + faceid as u32,
+            position: face.position.into(),
+            tex_info: ((face.texture.0 as u32) << 16u32)
+                + ((face.transform.bits() as u32) << 3)
+                + faceid as u32,
         },
     );
 }
@@ -55,29 +42,18 @@ impl BufferGroup {
     }
 }
 
-struct GeometryFaceRenderer<
-    Id: BlockId,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
-> {
-    phat: PhantomData<WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>,
+struct GeometryFaceRenderer<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize> {
+    phat: PhantomData<WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>>,
     program: Program,
     buffers: Vec<BufferGroup>,
 }
 
-impl<
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    > GeometryFaceRenderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    GeometryFaceRenderer<Id, CHUNK_SIZE, CHUNK_WIDTH>
 {
-    fn new<F: Facade>(facade: &F) -> Self {
-        let mut buffers = Vec::with_capacity(SIZE);
-        for _ in 0..SIZE {
+    fn new<F: Facade>(facade: &F, chunks: usize) -> Self {
+        let mut buffers = Vec::with_capacity(chunks);
+        for _ in 0..chunks {
             buffers.push(BufferGroup::new::<F, CHUNK_SIZE>(facade).unwrap());
         }
         Self {
@@ -86,45 +62,40 @@ impl<
             buffers,
         }
     }
+
+    fn prepare_chunk(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>, coord: ChunkCoord) {
+        let group = &mut self.buffers[info.world.index_of(coord).unwrap()];
+        let mut writevertex = group.vertex.map_write();
+        group.count = 0;
+        for face in visible_faces(&info.world, info.definitions, coord) {
+            // The geometry shader only expands axis-aligned faces
+            if face.shape == FaceShape::Cross {
+                continue;
+            }
+            gen_cube_mesh(&mut writevertex, &face, group.count);
+            group.count += 1;
+        }
+    }
 }
 
 struct GeometryFace;
 
-impl<
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    > Renderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
-    for GeometryFaceRenderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH> for GeometryFaceRenderer<Id, CHUNK_SIZE, CHUNK_WIDTH>
 {
-    fn prepare(&mut self, info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>) {
-        for ((chunk_pos, chunk), group) in info.world.as_ref().into_iter().zip(&mut self.buffers) {
-            let mut writevertex = group.vertex.map_write();
-            group.count = 0;
-            for (block_pos, id) in chunk.iter_solid() {
-                for face in BlockFace::iter() {
-                    gen_cube_mesh(
-                        &mut writevertex,
-                        chunk_pos,
-                        block_pos,
-                        info.definitions,
-                        id,
-                        group.count,
-                        face,
-                    );
-                    group.count += 1;
-                }
-            }
+    fn prepare(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>) {
+        for (coord, _) in &info.world {
+            self.prepare_chunk(info, coord);
         }
     }
 
-    fn render(
-        &self,
-        mut frame: glium::Frame,
-        info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>,
-    ) {
+    fn update(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>, dirty: &[ChunkCoord]) {
+        for &coord in dirty {
+            self.prepare_chunk(info, coord);
+        }
+    }
+
+    fn render(&self, mut frame: glium::Frame, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>) {
         frame.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
         let aspect_ratio = {
             let dim = frame.get_dimensions();
@@ -139,10 +110,17 @@ impl<
             .minify_filter(glium::uniforms::MinifySamplerFilter::LinearMipmapLinear)
             .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
             .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp);
+        let animations = info
+            .animations
+            .sampled()
+            .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest);
         let uniforms = uniform! {
             tile: sampled,
             perspective : perspective.to_cols_array_2d(),
             view_model: view_model,
+            animations: animations,
+            time: info.time_millis(),
         };
         for group in &self.buffers {
             let count = group.count as usize;
@@ -172,18 +150,14 @@ impl<
 }
 
 impl RendererProvider for GeometryFace {
-    fn get_renderer<
-        F: Facade,
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    >(
+    fn get_renderer<F: Facade, Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
         facade: &F,
-        _info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>,
-    ) -> Result<Box<dyn Renderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>> {
-        Ok(Box::new(GeometryFaceRenderer::new(facade)))
+        info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    ) -> Result<Box<dyn Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH>>> {
+        Ok(Box::new(GeometryFaceRenderer::new(
+            facade,
+            info.world.len(),
+        )))
     }
 }
 
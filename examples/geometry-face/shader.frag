@@ -4,7 +4,22 @@ layout(location = 0) in vec3 muv;
 layout(location = 0) out vec4 color;
 
 layout(location = 2) uniform sampler2DArray tile;
+layout(location = 3) uniform usampler1D animations;
+layout(location = 4) uniform uint time;
+
+// Layer showing now in place of `layer`, see `animation_table`
+float animate(float layer) {
+  uvec4 animation = texelFetch(animations, int(layer + 0.5), 0);
+  if (animation.x < 2u) {
+    return layer;
+  }
+  uint frame = (time / animation.y) % animation.x;
+  return float(texelFetch(animations, int(animation.z + frame), 0).x);
+}
 
 void main() {
-  color = texture(tile, muv);
+  color = texture(tile, vec3(muv.xy, animate(muv.z)));
+  if (color.a < 0.5) {
+    discard;
+  }
 }
\ No newline at end of file
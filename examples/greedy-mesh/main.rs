@@ -0,0 +1,182 @@
+use glam::f32 as math;
+use std::marker::PhantomData;
+
+use anyhow::Result;
+use glium::{
+    backend::Facade, implement_vertex, index::PrimitiveType, uniform, BackfaceCullingMode, Depth,
+    DrawParameters, Frame, IndexBuffer, Program, Surface, VertexBuffer,
+};
+use voxel_benchmark::{
+    mesher::{binary::BinaryGreedy, greedy::Greedy, Mesh, Mesher},
+    *,
+};
+
+#[derive(Copy, Clone)]
+struct PosTex {
+    position: [f32; 3],
+    tex_info: u32,
+    tint: u32,
+}
+
+implement_vertex!(PosTex, position, tex_info, tint);
+
+struct BufferGroup {
+    vertex: VertexBuffer<PosTex>,
+    index: IndexBuffer<u32>,
+    count: u32,
+}
+
+impl BufferGroup {
+    fn new<F: Facade, const CHUNK_SIZE: usize>(facade: &F) -> Result<Self> {
+        let vertex = VertexBuffer::empty_dynamic(facade, CHUNK_SIZE * 4 * 6)?;
+        let index =
+            IndexBuffer::empty_dynamic(facade, PrimitiveType::TrianglesList, CHUNK_SIZE * 6 * 6)?;
+        Ok(Self {
+            vertex,
+            index,
+            count: 0,
+        })
+    }
+
+    fn upload(&mut self, mesh: &Mesh) {
+        self.count = mesh.face_count() as u32;
+        if mesh.is_empty() {
+            return;
+        }
+        let vertices: Vec<PosTex> = mesh
+            .positions
+            .iter()
+            .zip(&mesh.tex_info)
+            .zip(&mesh.tints)
+            .map(|((&position, &tex_info), &tint)| PosTex {
+                position,
+                tex_info,
+                tint,
+            })
+            .collect();
+        self.vertex
+            .slice(0..vertices.len())
+            .unwrap()
+            .write(&vertices);
+        self.index
+            .slice(0..mesh.indices.len())
+            .unwrap()
+            .write(&mesh.indices);
+    }
+}
+
+struct GreedyRenderer<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize> {
+    phat: PhantomData<WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>>,
+    program: Program,
+    buffers: Vec<BufferGroup>,
+    mesher: Box<dyn Mesher<Id, CHUNK_SIZE, CHUNK_WIDTH, DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH>>>,
+}
+
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    GreedyRenderer<Id, CHUNK_SIZE, CHUNK_WIDTH>
+{
+    fn new<F: Facade>(facade: &F, chunks: usize) -> Self {
+        let mut buffers = Vec::with_capacity(chunks);
+        for _ in 0..chunks {
+            buffers.push(BufferGroup::new::<F, CHUNK_SIZE>(facade).unwrap());
+        }
+        Self {
+            phat: Default::default(),
+            program: shader_program!(facade, "shader").unwrap(),
+            buffers,
+            mesher: match std::env::var("VOXEL_MESHER").as_deref() {
+                Ok("binary") => Box::new(BinaryGreedy),
+                _ => Box::new(Greedy),
+            },
+        }
+    }
+}
+
+struct GreedyMesh;
+
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH> for GreedyRenderer<Id, CHUNK_SIZE, CHUNK_WIDTH>
+{
+    fn prepare(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>) {
+        let (world, climate) = (&info.world, info.climate.as_ref());
+        for ((coord, _), group) in world.into_iter().zip(&mut self.buffers) {
+            group.upload(&self.mesher.mesh_in(world, info.definitions, coord, climate));
+        }
+    }
+
+    fn update(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>, dirty: &[ChunkCoord]) {
+        let (world, climate) = (&info.world, info.climate.as_ref());
+        for &coord in dirty {
+            let mesh = self.mesher.mesh_in(world, info.definitions, coord, climate);
+            self.buffers[world.index_of(coord).unwrap()].upload(&mesh);
+        }
+    }
+
+    fn render(&self, mut frame: Frame, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>) {
+        frame.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
+        let aspect_ratio = {
+            let dim = frame.get_dimensions();
+            dim.0 as f32 / dim.1 as f32
+        };
+        let perspective =
+            math::Mat4::perspective_rh_gl(f32::to_radians(90.0), aspect_ratio, 0.1, 1024.0);
+        let view_model = info.camera.get_matrix();
+        let sampled = info
+            .texture
+            .sampled()
+            .minify_filter(glium::uniforms::MinifySamplerFilter::LinearMipmapLinear)
+            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
+            .wrap_function(glium::uniforms::SamplerWrapFunction::Repeat);
+        let animations = info
+            .animations
+            .sampled()
+            .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest);
+        let uniforms = uniform! {
+            tile: sampled,
+            perspective : perspective.to_cols_array_2d(),
+            view_model: view_model,
+            animations: animations,
+            time: info.time_millis(),
+        };
+        for group in &self.buffers {
+            let count = group.count as usize;
+            if count == 0 {
+                continue;
+            }
+            frame
+                .draw(
+                    group.vertex.slice(0..count * 4).unwrap(),
+                    group.index.slice(0..count * 6).unwrap(),
+                    &self.program,
+                    &uniforms,
+                    &DrawParameters {
+                        depth: Depth {
+                            test: glium::DepthTest::IfLess,
+                            write: true,
+                            ..Default::default()
+                        },
+                        backface_culling: BackfaceCullingMode::CullClockwise,
+                        smooth: Some(glium::Smooth::Nicest),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+        frame.finish().unwrap();
+    }
+}
+
+impl RendererProvider for GreedyMesh {
+    fn get_renderer<F: Facade, Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
+        facade: &F,
+        info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    ) -> Result<Box<dyn Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH>>> {
+        Ok(Box::new(GreedyRenderer::new(facade, info.world.len())))
+    }
+}
+
+pub fn main() -> Result<()> {
+    env_logger::Builder::from_default_env().init();
+    run_renderer::<GreedyMesh>()
+}
@@ -0,0 +1,19 @@
+#version 450
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in uint tex_info;
+layout(location = 2) in uint tint;
+layout(location = 0) out vec3 muv;
+layout(location = 1) out vec3 mtint;
+
+layout(location = 0) uniform mat4 perspective;
+layout(location = 1) uniform mat4 view_model;
+
+void main() {
+  float repeat_u = float((tex_info >> 2) & 0x7F);
+  float repeat_v = float((tex_info >> 9) & 0x7F);
+  muv = vec3(float((tex_info & 2) >> 1) * repeat_u,
+             float(tex_info & 1) * repeat_v, float(tex_info >> 16));
+  mtint = unpackUnorm4x8(tint).rgb;
+  gl_Position = perspective * view_model * vec4(position, 1.0);
+}
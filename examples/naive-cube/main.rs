@@ -2,78 +2,23 @@ use glam::f32 as math;
 use std::marker::PhantomData;
 
 use anyhow::Result;
-use enum_map::EnumMap;
 use glium::{
-    backend::Facade, buffer::WriteMapping, implement_vertex, index::PrimitiveType, uniform,
-    BackfaceCullingMode, Depth, DrawParameters, Frame, IndexBuffer, Program, Surface, VertexBuffer,
+    backend::Facade, implement_vertex, index::PrimitiveType, uniform, BackfaceCullingMode, Depth,
+    DrawParameters, Frame, IndexBuffer, Program, Surface, VertexBuffer,
+};
+use voxel_benchmark::{
+    mesher::{culling::Culling, Mesh, Mesher},
+    *,
 };
-use strum::IntoEnumIterator;
-use voxel_benchmark::*;
 
 #[derive(Copy, Clone)]
 struct PosTex {
     position: [f32; 3],
     tex_info: u32,
+    tint: u32,
 }
 
-implement_vertex!(PosTex, position, tex_info);
-
-fn gen_cube_mesh<
-    Id: BlockId,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
->(
-    vertex: &mut WriteMapping<[PosTex]>,
-    index: &mut WriteMapping<[u32]>,
-    chunk_pos: ChunkPos<SIZE, WIDTH>,
-    block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
-    definitions: &EnumMap<Id, SolidBlockDefinition>,
-    id: Id,
-    current: u32,
-    face: BlockFace,
-) {
-    let vertex_base = current * 4;
-    let index_base = current * 6;
-    &[0, 1, 2, 0, 2, 3]
-        .iter()
-        .map(|x| *x + vertex_base)
-        .zip((0..6usize).into_iter().map(|x| x + index_base as usize))
-        .for_each(|(value, i)| index.set(i, value));
-    let origin = WorldPosition::from((chunk_pos, block_pos));
-    match face {
-        BlockFace::North => [origin.ix().iy(), origin.ix(), origin, origin.iy()],
-        BlockFace::South => [
-            origin.iz().iy(),
-            origin.iz(),
-            origin.iz().ix(),
-            origin.iz().ix().iy(),
-        ],
-        BlockFace::East => [
-            origin.ix().iy().iz(),
-            origin.ix().iz(),
-            origin.ix(),
-            origin.ix().iy(),
-        ],
-        BlockFace::West => [origin.iy(), origin, origin.iz(), origin.iz().iy()],
-        BlockFace::Up => [
-            origin.iy(),
-            origin.iy().iz(),
-            origin.iy().iz().ix(),
-            origin.iy().ix(),
-        ],
-        BlockFace::Down => [origin.iz(), origin, origin.ix(), origin.ix().iz()],
-    }
-    .iter()
-    .zip(&definitions[id][face].into_arr())
-    .map(|(&position, &uv)| PosTex {
-        position: position.into(),
-        tex_info: uv.into(),
-    })
-    .zip((0..4usize).into_iter().map(|x| x + vertex_base as usize))
-    .for_each(|(value, i)| vertex.set(i, value));
-}
+implement_vertex!(PosTex, position, tex_info, tint);
 
 struct BufferGroup {
     vertex: VertexBuffer<PosTex>,
@@ -92,31 +37,46 @@ impl BufferGroup {
             count: 0,
         })
     }
+
+    fn upload(&mut self, mesh: &Mesh) {
+        self.count = mesh.face_count() as u32;
+        if mesh.is_empty() {
+            return;
+        }
+        let vertices: Vec<PosTex> = mesh
+            .positions
+            .iter()
+            .zip(&mesh.tex_info)
+            .zip(&mesh.tints)
+            .map(|((&position, &tex_info), &tint)| PosTex {
+                position,
+                tex_info,
+                tint,
+            })
+            .collect();
+        self.vertex
+            .slice(0..vertices.len())
+            .unwrap()
+            .write(&vertices);
+        self.index
+            .slice(0..mesh.indices.len())
+            .unwrap()
+            .write(&mesh.indices);
+    }
 }
 
-struct BasicRenderer<
-    Id: BlockId,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
-> {
-    phat: PhantomData<WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>,
+struct BasicRenderer<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize> {
+    phat: PhantomData<WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>>,
     program: Program,
     buffers: Vec<BufferGroup>,
 }
 
-impl<
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    > BasicRenderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    BasicRenderer<Id, CHUNK_SIZE, CHUNK_WIDTH>
 {
-    fn new<F: Facade>(facade: &F) -> Self {
-        let mut buffers = Vec::with_capacity(SIZE);
-        for _ in 0..SIZE {
+    fn new<F: Facade>(facade: &F, chunks: usize) -> Self {
+        let mut buffers = Vec::with_capacity(chunks);
+        for _ in 0..chunks {
             buffers.push(BufferGroup::new::<F, CHUNK_SIZE>(facade).unwrap());
         }
         Self {
@@ -129,39 +89,25 @@ impl<
 
 struct Basic;
 
-impl<
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    > Renderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
-    for BasicRenderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH> for BasicRenderer<Id, CHUNK_SIZE, CHUNK_WIDTH>
 {
-    fn prepare(&mut self, info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>) {
-        for ((chunk_pos, chunk), group) in info.world.as_ref().into_iter().zip(&mut self.buffers) {
-            let mut writevertex = group.vertex.map_write();
-            let mut writeindex = group.index.map_write();
-            group.count = 0;
-            for (block_pos, id) in chunk.iter_solid() {
-                for face in BlockFace::iter() {
-                    gen_cube_mesh(
-                        &mut writevertex,
-                        &mut writeindex,
-                        chunk_pos,
-                        block_pos,
-                        info.definitions,
-                        id,
-                        group.count,
-                        face,
-                    );
-                    group.count += 1;
-                }
-            }
+    fn prepare(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>) {
+        let (world, climate) = (&info.world, info.climate.as_ref());
+        for ((coord, _), group) in world.into_iter().zip(&mut self.buffers) {
+            group.upload(&Culling.mesh_in(world, info.definitions, coord, climate));
+        }
+    }
+
+    fn update(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>, dirty: &[ChunkCoord]) {
+        let (world, climate) = (&info.world, info.climate.as_ref());
+        for &coord in dirty {
+            let mesh = Culling.mesh_in(world, info.definitions, coord, climate);
+            self.buffers[world.index_of(coord).unwrap()].upload(&mesh);
         }
     }
 
-    fn render(&self, mut frame: Frame, info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>) {
+    fn render(&self, mut frame: Frame, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>) {
         frame.clear_color_and_depth((0.0, 0.0, 0.0, 1.0), 1.0);
         let aspect_ratio = {
             let dim = frame.get_dimensions();
@@ -176,10 +122,17 @@ impl<
             .minify_filter(glium::uniforms::MinifySamplerFilter::LinearMipmapLinear)
             .magnify_filter(glium::uniforms::MagnifySamplerFilter::Linear)
             .wrap_function(glium::uniforms::SamplerWrapFunction::Clamp);
+        let animations = info
+            .animations
+            .sampled()
+            .minify_filter(glium::uniforms::MinifySamplerFilter::Nearest)
+            .magnify_filter(glium::uniforms::MagnifySamplerFilter::Nearest);
         let uniforms = uniform! {
             tile: sampled,
             perspective : perspective.to_cols_array_2d(),
             view_model: view_model,
+            animations: animations,
+            time: info.time_millis(),
         };
         for group in &self.buffers {
             let count = group.count as usize;
@@ -210,18 +163,11 @@ impl<
 }
 
 impl RendererProvider for Basic {
-    fn get_renderer<
-        F: Facade,
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    >(
+    fn get_renderer<F: Facade, Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
         facade: &F,
-        _info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>,
-    ) -> Result<Box<dyn Renderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>> {
-        Ok(Box::new(BasicRenderer::new(facade)))
+        info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    ) -> Result<Box<dyn Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH>>> {
+        Ok(Box::new(BasicRenderer::new(facade, info.world.len())))
     }
 }
 
@@ -2,12 +2,15 @@
 
 layout(location = 0) in vec3 position;
 layout(location = 1) in uint tex_info;
+layout(location = 2) in uint tint;
 layout(location = 0) out vec3 muv;
+layout(location = 1) out vec3 mtint;
 
 layout(location = 0) uniform mat4 perspective;
 layout(location = 1) uniform mat4 view_model;
 
 void main() {
   muv = vec3(float((tex_info & 2) >> 1), float(tex_info & 1), float(tex_info >> 16));
+  mtint = unpackUnorm4x8(tint).rgb;
   gl_Position = perspective * view_model * vec4(position, 1.0);
 }
\ No newline at end of file
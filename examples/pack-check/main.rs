@@ -0,0 +1,26 @@
+use std::env;
+
+use anyhow::{bail, Result};
+use voxel_benchmark::packs::{basic::BasicPack, loader::LoadedPack, Pack};
+
+/// Validates a pack, printing every problem found
+///
+/// Usage: `pack-check` for the embedded pack or `pack-check <atlas.xml>
+/// <blocks.txt>` for one on disk; fails when the pack has errors, warnings are
+/// only printed.
+fn main() -> Result<()> {
+    env_logger::Builder::from_default_env().init();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let report = match args.as_slice() {
+        [] => BasicPack::validate(),
+        [atlas, blocks] => LoadedPack::load(atlas, blocks)?.validate(),
+        _ => bail!("usage: pack-check [<atlas.xml> <blocks.txt>]"),
+    };
+    print!("{}", report);
+    let errors = report.errors().count();
+    println!("{} errors, {} warnings", errors, report.warnings().count());
+    if errors > 0 {
+        bail!("the pack has errors");
+    }
+    Ok(())
+}
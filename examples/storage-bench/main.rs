@@ -0,0 +1,71 @@
+use std::{fmt::Display, time::Instant};
+
+use anyhow::Result;
+use voxel_benchmark::{
+    generator::{
+        flat::Flat,
+        heightmap::Heightmap,
+        random::{RandomFill, RandomGenerator},
+        WorldGenerator,
+    },
+    mesher::{culling::Culling, Mesher},
+    packs::{basic::*, Pack},
+    *,
+};
+
+const SIZE: usize = 16;
+const WIDTH: usize = 4;
+const CHUNK_SIZE: usize = 8192;
+const CHUNK_WIDTH: usize = 16;
+/// Every storage gets the same random worlds
+const SEED: u64 = 0;
+
+fn bench<S: ChunkStorage<BasicId, CHUNK_SIZE, CHUNK_WIDTH>>(
+    storage: &str,
+    scene: impl Display,
+    generator: &dyn WorldGenerator<BasicId, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>,
+) {
+    let start = Instant::now();
+    let mut world = World::<BasicId, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>::create();
+    generator.generate(world.as_mut());
+    let generated = start.elapsed();
+    let memory: usize = world
+        .as_ref()
+        .into_iter()
+        .map(|(_, chunk)| chunk.memory_usage())
+        .sum();
+    let start = Instant::now();
+    let faces: usize = world
+        .as_ref()
+        .into_iter()
+        .map(|(pos, _)| {
+            Culling
+                .mesh(world.as_ref(), BasicPack::get_map(), pos.into())
+                .face_count()
+        })
+        .sum();
+    let meshed = start.elapsed();
+    println!(
+        "{:<8} {:<7} generate {:>10.2?} mesh {:>10.2?} memory {:>9} bytes faces {}",
+        storage, scene, generated, meshed, memory, faces
+    );
+}
+
+fn bench_storage<S: ChunkStorage<BasicId, CHUNK_SIZE, CHUNK_WIDTH>>(storage: &str) {
+    bench::<S>(storage, "flat", &Flat::new_simple(16));
+    bench::<S>(storage, "odd", &RandomGenerator::new(RandomFill::Odd, SEED));
+    bench::<S>(
+        storage,
+        "fill",
+        &RandomGenerator::new(RandomFill::Fill, SEED),
+    );
+    bench::<S>(storage, "terrain", &Heightmap::basic(SEED, 16));
+}
+
+fn main() -> Result<()> {
+    env_logger::Builder::from_default_env().init();
+    bench_storage::<Chunk<BasicId, CHUNK_SIZE, CHUNK_WIDTH>>("dense");
+    bench_storage::<PaletteChunk<BasicId, CHUNK_SIZE, CHUNK_WIDTH>>("palette");
+    bench_storage::<OctreeChunk<BasicId, CHUNK_SIZE, CHUNK_WIDTH>>("octree");
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::{env, fmt::Display, time::Instant};
+
+use anyhow::Result;
+use voxel_benchmark::{
+    generator::{
+        flat::Flat,
+        heightmap::Heightmap,
+        random::{RandomFill, RandomGenerator},
+        ChunkGenerator,
+    },
+    mesher::{binary::BinaryGreedy, culling::Culling, greedy::Greedy, Mesher},
+    packs::{basic::*, Pack},
+    *,
+};
+
+const CHUNK_SIZE: usize = 8192;
+const CHUNK_WIDTH: usize = 16;
+/// Seed of the random scenes, their hash is printed to compare runs
+const SEED: u64 = 0;
+
+type SweepWorld = DynamicWorld<BasicId, CHUNK_SIZE, CHUNK_WIDTH>;
+
+fn bench(
+    side: usize,
+    scene: impl Display,
+    generator: &dyn ChunkGenerator<BasicId, CHUNK_SIZE, CHUNK_WIDTH>,
+    meshers: &[(
+        &str,
+        &dyn Mesher<BasicId, CHUNK_SIZE, CHUNK_WIDTH, SweepWorld>,
+    )],
+) {
+    let start = Instant::now();
+    let mut world = SweepWorld::new(side, side);
+    world.generate(generator);
+    let generated = start.elapsed();
+    println!(
+        "{:>3}x{:<3} {:<7} world hash {:016x}",
+        side,
+        side,
+        scene,
+        world_hash(&world)
+    );
+    for (name, mesher) in meshers {
+        let start = Instant::now();
+        let faces: usize = world
+            .into_iter()
+            .map(|(coord, _)| {
+                mesher
+                    .mesh(&world, BasicPack::get_map(), coord)
+                    .face_count()
+            })
+            .sum();
+        let meshed = start.elapsed();
+        // Same meshes with the per-vertex tint looked up in the generator climate
+        let start = Instant::now();
+        for (coord, _) in &world {
+            mesher.mesh_in(&world, BasicPack::get_map(), coord, generator);
+        }
+        let tinted = start.elapsed();
+        println!(
+            "{:>3}x{:<3} {:<7} {:<8} generate {:>10.2?} mesh {:>10.2?} tinted {:>10.2?} faces {}",
+            side, side, scene, name, generated, meshed, tinted, faces
+        );
+    }
+}
+
+/// Sweeps square worlds of the sizes (in chunks) given on the command line,
+/// or 1, 2, 4, 8 and 16 by default
+fn main() -> Result<()> {
+    env_logger::Builder::from_default_env().init();
+    let sides = env::args()
+        .skip(1)
+        .map(|arg| arg.parse())
+        .collect::<Result<Vec<usize>, _>>()?;
+    let sides = if sides.is_empty() {
+        vec![1, 2, 4, 8, 16]
+    } else {
+        sides
+    };
+    let meshers: [(&str, &dyn Mesher<_, CHUNK_SIZE, CHUNK_WIDTH, SweepWorld>); 3] = [
+        ("culling", &Culling),
+        ("greedy", &Greedy),
+        ("binary", &BinaryGreedy),
+    ];
+    for side in sides {
+        bench(side, "flat", &Flat::new_simple(16), &meshers);
+        let odd = RandomGenerator::new(RandomFill::Odd, SEED);
+        bench(side, "odd", &odd, &meshers);
+        let fill = RandomGenerator::new(RandomFill::Fill, SEED);
+        bench(side, "fill", &fill, &meshers);
+        bench(side, "terrain", &Heightmap::basic(SEED, 16), &meshers);
+        // Connected textures look up the neighbours of every face
+        let panes = Flat::new(vec![Some(BasicId::GlassFrame); 16]);
+        bench(side, "panes", &panes, &meshers);
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use crate::generator::mix_seed;
+
+/// Climate of a block column, both values in `0.0..=1.0`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Climate {
+    pub temperature: f32,
+    pub humidity: f32,
+}
+
+impl Default for Climate {
+    /// Temperate, neither dry nor wet
+    fn default() -> Self {
+        Self {
+            temperature: 0.5,
+            humidity: 0.5,
+        }
+    }
+}
+
+/// Blocks between the lattice points of `Climate::at`
+const CLIMATE_SCALE: i32 = 64;
+
+impl Climate {
+    /// Smooth value noise over the columns of a world generated from `seed`,
+    /// what generators without a climate of their own report
+    pub fn at(seed: u64, x: i32, z: i32) -> Self {
+        Self {
+            temperature: value_noise(seed, x, z, 0),
+            humidity: value_noise(seed, x, z, 1),
+        }
+    }
+}
+
+/// Climate of every column of a world, exposed by generators; meshes look it up
+/// per vertex to tint faces (see `Tint`)
+pub trait ClimateSource {
+    /// Climate of the column at world block coordinates `(x, z)`, by default
+    /// that of seed 0; seeded generators pass their seed to `Climate::at`
+    fn climate(&self, x: i32, z: i32) -> Climate {
+        Climate::at(0, x, z)
+    }
+}
+
+/// The same climate everywhere
+impl ClimateSource for Climate {
+    fn climate(&self, _x: i32, _z: i32) -> Climate {
+        *self
+    }
+}
+
+/// Colour a face texture is multiplied with, depending on the climate
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Tint {
+    /// Shown as is
+    #[default]
+    None,
+    Grass,
+    Foliage,
+}
+
+impl Tint {
+    /// Multiplier as `[r, g, b]`, blending the colours of the climate extremes
+    pub fn color(self, climate: Climate) -> [u8; 3] {
+        // Cold and dry, cold and wet, hot and dry, hot and wet
+        let corners: [[f32; 3]; 4] = match self {
+            Tint::None => return [255; 3],
+            Tint::Grass => [
+                [200.0, 220.0, 210.0],
+                [170.0, 225.0, 200.0],
+                [255.0, 230.0, 150.0],
+                [190.0, 255.0, 160.0],
+            ],
+            Tint::Foliage => [
+                [210.0, 215.0, 200.0],
+                [160.0, 210.0, 190.0],
+                [255.0, 215.0, 140.0],
+                [170.0, 255.0, 150.0],
+            ],
+        };
+        let (t, h) = (
+            climate.temperature.clamp(0.0, 1.0),
+            climate.humidity.clamp(0.0, 1.0),
+        );
+        let mut color = [0; 3];
+        for (channel, value) in color.iter_mut().enumerate() {
+            let cold = lerp(corners[0][channel], corners[1][channel], h);
+            let hot = lerp(corners[2][channel], corners[3][channel], h);
+            *value = lerp(cold, hot, t).round() as u8;
+        }
+        color
+    }
+
+    /// `color` packed for a vertex attribute, red in the low byte and an opaque
+    /// alpha, as GLSL `unpackUnorm4x8` reads it
+    pub fn packed(self, climate: Climate) -> u32 {
+        let [r, g, b] = self.color(climate);
+        u32::from_le_bytes([r, g, b, 255])
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Value in `0.0..=1.0` hashed from a lattice point
+fn lattice(seed: u64, x: i32, z: i32, salt: u32) -> f32 {
+    let hash = mix_seed(seed, &[x as u32 as u64, z as u32 as u64, salt as u64]);
+    (hash >> 40) as f32 / (1 << 24) as f32
+}
+
+/// Lattice values blended with a smoothstep between the points
+fn value_noise(seed: u64, x: i32, z: i32, salt: u32) -> f32 {
+    let (cx, cz) = (x.div_euclid(CLIMATE_SCALE), z.div_euclid(CLIMATE_SCALE));
+    let smooth = |offset: i32| {
+        let t = offset as f32 / CLIMATE_SCALE as f32;
+        t * t * (3.0 - 2.0 * t)
+    };
+    let (tx, tz) = (
+        smooth(x.rem_euclid(CLIMATE_SCALE)),
+        smooth(z.rem_euclid(CLIMATE_SCALE)),
+    );
+    let lattice = |x, z| lattice(seed, x, z, salt);
+    let near = lerp(lattice(cx, cz), lattice(cx + 1, cz), tx);
+    let far = lerp(lattice(cx, cz + 1), lattice(cx + 1, cz + 1), tx);
+    lerp(near, far, tz)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn climate_varies_smoothly() {
+        let mut seen = (f32::MAX, f32::MIN);
+        for x in -200..200 {
+            let here = Climate::at(4, x, 17);
+            let next = Climate::at(4, x + 1, 17);
+            assert!((0.0..=1.0).contains(&here.temperature));
+            assert!((0.0..=1.0).contains(&here.humidity));
+            assert!((here.temperature - next.temperature).abs() < 0.05);
+            seen = (seen.0.min(here.temperature), seen.1.max(here.temperature));
+        }
+        assert!(seen.1 - seen.0 > 0.1, "{:?}", seen);
+        assert_eq!(Climate::at(4, -70, 3), Climate::at(4, -70, 3));
+        assert_ne!(Climate::at(4, -70, 3), Climate::at(5, -70, 3));
+    }
+
+    #[test]
+    fn tints_follow_climate() {
+        let climate = |temperature, humidity| Climate {
+            temperature,
+            humidity,
+        };
+        assert_eq!(Tint::None.color(climate(0.0, 1.0)), [255; 3]);
+        assert_eq!(Tint::Grass.color(climate(1.0, 0.0)), [255, 230, 150]);
+        assert_ne!(
+            Tint::Grass.color(climate(0.2, 0.5)),
+            Tint::Grass.color(climate(0.8, 0.5))
+        );
+        assert_eq!(
+            Tint::Foliage.packed(climate(0.0, 0.0)),
+            u32::from_le_bytes([210, 215, 200, 255])
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::fmt::Display;
 
 use enum_map::Enum;
+use strum::IntoEnumIterator;
 
 use crate::SolidBlockDefinition;
 
@@ -14,32 +15,94 @@ impl<T> BlockId for T where
 {
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How a block is drawn and which neighbouring faces it hides
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockKind {
+    /// Full cube hiding every face that touches it
+    #[default]
+    Opaque,
+    /// Full cube that can be seen through, faces between two blocks of the same
+    /// id are hidden
+    Transparent,
+    /// Two crossed quads (plants), neither hiding nor hidden by neighbours
+    Cross,
+    /// See-through cube like `Transparent`, whose exposed surface sits lower
+    Liquid,
+}
+
+impl BlockKind {
+    /// Whether this kind is drawn as the faces of a cube
+    pub fn is_cube(self) -> bool {
+        !matches!(self, BlockKind::Cross)
+    }
+}
+
+/// Per-block state (orientation, growth stage, variant, ...), `0` is the default
+///
+/// What a state means depends on the block id; packs describe it with a
+/// `StateKind` and pick textures for it through `SolidBlockDefinition`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockState(pub u8);
+
+impl Display for BlockState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Typed set of states for one kind of block, stored as the position of the
+/// variant in `iter()`, so the first variant is the default state
+pub trait StateKind: IntoEnumIterator + PartialEq + Copy {
+    fn into_state(self) -> BlockState {
+        BlockState(Self::iter().position(|kind| kind == self).unwrap() as u8)
+    }
+
+    fn from_state(state: BlockState) -> Option<Self> {
+        Self::iter().nth(state.0 as usize)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Block<Id: BlockId> {
+    #[default]
     Empty,
-    Solid { id: Id },
+    Solid {
+        id: Id,
+        state: BlockState,
+    },
 }
 
 impl<Id: BlockId> Display for Block<Id> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
             Block::Empty => write!(f, "<empty>"),
-            Block::Solid { id } => write!(f, "{}", id),
+            Block::Solid {
+                id,
+                state: BlockState(0),
+            } => write!(f, "{}", id),
+            Block::Solid { id, state } => write!(f, "{}#{}", id, state),
         }
     }
 }
 
-impl<Id: BlockId> Default for Block<Id> {
-    fn default() -> Self {
-        Self::Empty
+impl<Id: BlockId> Block<Id> {
+    /// Solid block in its default state
+    pub fn solid(id: Id) -> Self {
+        Block::Solid {
+            id,
+            state: BlockState::default(),
+        }
     }
-}
 
-impl<Id: BlockId> Block<Id> {
-    pub fn is_empty(&self) -> bool {
-        match self {
-            &Block::Empty => true,
-            _ => false,
+    /// Solid block in the typed state `kind`
+    pub fn with_state(id: Id, kind: impl StateKind) -> Self {
+        Block::Solid {
+            id,
+            state: kind.into_state(),
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Block::Empty)
+    }
 }
@@ -19,7 +19,7 @@ impl CameraCreation for ModelCamera {
         Self {
             x: 0f32,
             y: (height / 2) as f32,
-            z: (length.max(width) as f32) * -1.0,
+            z: -(length.max(width) as f32),
             width,
             height,
             length,
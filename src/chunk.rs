@@ -4,12 +4,76 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-use crate::{Block, BlockId};
+use enum_map::Enum;
+
+use crate::{Block, BlockId, SolidBlockDefinition};
 
 const fn calc_height(size: usize, width: usize) -> usize {
     size / width / width
 }
 
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+
+/// Feed `bytes` to the FNV-1a `hash`
+pub(crate) fn fnv(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+/// Backing store of a chunk's blocks, so `World` can swap storage layouts
+pub trait ChunkStorage<Id: BlockId, const SIZE: usize, const WIDTH: usize>:
+    Default + Index<BlockSubPos<SIZE, WIDTH>, Output = Block<Id>>
+{
+    fn set(&mut self, pos: BlockSubPos<SIZE, WIDTH>, block: Block<Id>);
+
+    /// Approximate number of bytes used by this chunk, including heap allocations
+    fn memory_usage(&self) -> usize;
+
+    fn get(&self, pos: BlockSubPos<SIZE, WIDTH>) -> Block<Id> {
+        self[pos]
+    }
+
+    /// Overwrite every block with the result of `f`
+    fn fill(&mut self, mut f: impl FnMut(BlockSubPos<SIZE, WIDTH>) -> Block<Id>) {
+        for pos in BlockSubPosIterator::default() {
+            self.set(pos, f(pos));
+        }
+    }
+
+    fn iter_blocks(&self) -> impl Iterator<Item = (BlockSubPos<SIZE, WIDTH>, Block<Id>)> + '_ {
+        BlockSubPosIterator::default().map(move |pos| (pos, self[pos]))
+    }
+
+    /// FNV-1a hash of the blocks in position order, the same on every machine
+    /// and run (unlike `std::hash`), to compare generated chunks
+    fn content_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+        for (_, block) in self.iter_blocks() {
+            let (id, state) = match block {
+                Block::Empty => (0, 0),
+                Block::Solid { id, state } => (
+                    <Id as Enum<SolidBlockDefinition>>::to_usize(id) + 1,
+                    state.0,
+                ),
+            };
+            hash = fnv(hash, &(id as u16).to_le_bytes());
+            hash = fnv(hash, &[state]);
+        }
+        hash
+    }
+
+    fn iter_solid(&self) -> impl Iterator<Item = (BlockSubPos<SIZE, WIDTH>, Id)> + '_ {
+        self.iter_blocks().filter_map(|(pos, blk)| {
+            if let Block::Solid { id, .. } = blk {
+                Some((pos, id))
+            } else {
+                None
+            }
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Chunk<Id: BlockId, const SIZE: usize, const WIDTH: usize>(pub [Block<Id>; SIZE]);
 
@@ -76,9 +140,9 @@ impl<const SIZE: usize, const WIDTH: usize> Display for BlockSubPos<SIZE, WIDTH>
     }
 }
 
-impl<const SIZE: usize, const WIDTH: usize> Into<(u16, u16, u16)> for BlockSubPos<SIZE, WIDTH> {
-    fn into(self) -> (u16, u16, u16) {
-        let input = self.0;
+impl<const SIZE: usize, const WIDTH: usize> From<BlockSubPos<SIZE, WIDTH>> for (u16, u16, u16) {
+    fn from(pos: BlockSubPos<SIZE, WIDTH>) -> Self {
+        let input = pos.0;
         let x = (input % WIDTH) as u16;
         let z = ((input / WIDTH) % WIDTH) as u16;
         let y = (input / WIDTH / WIDTH) as u16;
@@ -96,6 +160,15 @@ impl<const SIZE: usize, const WIDTH: usize> BlockSubPos<SIZE, WIDTH> {
         Self((x as usize) + (z as usize + (y as usize) * WIDTH) * WIDTH)
     }
 
+    /// Like `new`, but returns `None` for coordinates outside the chunk
+    pub fn try_new(x: u16, y: u16, z: u16) -> Option<Self> {
+        if (x as usize) < WIDTH && (y as usize) < Self::HEIGHT && (z as usize) < WIDTH {
+            Some(Self::new(x, y, z))
+        } else {
+            None
+        }
+    }
+
     pub fn from_index(input: usize) -> Self {
         assert!(input < SIZE);
         Self(input)
@@ -150,14 +223,20 @@ impl<'chunk, Id: BlockId, const SIZE: usize, const WIDTH: usize> IntoIterator
     }
 }
 
-impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> Chunk<Id, SIZE, WIDTH> {
-    pub fn iter_solid(&self) -> impl Iterator<Item = (BlockSubPos<SIZE, WIDTH>, Id)> + '_ {
-        self.into_iter().filter_map(|(pos, blk)| {
-            if let &Block::Solid { id } = blk {
-                Some((pos, id))
-            } else {
-                None
-            }
-        })
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> ChunkStorage<Id, SIZE, WIDTH>
+    for Chunk<Id, SIZE, WIDTH>
+{
+    fn set(&mut self, pos: BlockSubPos<SIZE, WIDTH>, block: Block<Id>) {
+        self[pos] = block;
+    }
+
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
+
+    fn fill(&mut self, mut f: impl FnMut(BlockSubPos<SIZE, WIDTH>) -> Block<Id>) {
+        for (pos, block) in self {
+            *block = f(pos);
+        }
     }
 }
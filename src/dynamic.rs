@@ -0,0 +1,358 @@
+use std::{
+    fmt::Display,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+use crate::{
+    affected_chunks, generator::ChunkGenerator, Block, BlockId, Chunk, ChunkCoord, ChunkSource,
+    ChunkStorage, OutOfWorld, WorldPosition,
+};
+
+/// Bounded world whose size is chosen at runtime
+///
+/// Chunks are laid out row by row like in `World`, `width` chunks along x and
+/// `length` chunks along z. Use `World` when the size is known at compile time.
+///
+/// Like in `World`, chunks modified through `set`, `chunk_mut`, `IndexMut` or
+/// mutable iteration are flagged dirty until `take_dirty` is called.
+#[derive(Debug, Clone)]
+pub struct DynamicWorld<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> = Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+> {
+    width: usize,
+    length: usize,
+    chunks: Vec<S>,
+    dirty: Vec<bool>,
+    phat: PhantomData<Id>,
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> + Display,
+    > Display for DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "DynamicWorld<{}, {}, {}> {}x{}",
+            std::any::type_name::<Id>(),
+            CHUNK_SIZE,
+            CHUNK_WIDTH,
+            self.width,
+            self.length
+        )?;
+        for (pos, chunk) in self {
+            writeln!(f, "{}: {}", pos, chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    /// Empty world of `width` by `length` chunks
+    pub fn new(width: usize, length: usize) -> Self {
+        let mut chunks = Vec::with_capacity(width * length);
+        chunks.resize_with(width * length, S::default);
+        Self {
+            width,
+            length,
+            chunks,
+            dirty: vec![true; width * length],
+            phat: PhantomData,
+        }
+    }
+
+    /// Number of chunks along x
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of chunks along z
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Position of the chunk at `coord` in iteration order, or `None` outside
+    /// the world
+    pub fn index_of(&self, coord: ChunkCoord) -> Option<usize> {
+        if coord.x < 0
+            || coord.z < 0
+            || coord.x as usize >= self.width
+            || coord.z as usize >= self.length
+        {
+            return None;
+        }
+        Some(coord.x as usize + coord.z as usize * self.width)
+    }
+
+    /// Chunk at `coord`, or `None` outside the world
+    pub fn chunk(&self, coord: ChunkCoord) -> Option<&S> {
+        self.index_of(coord).map(|index| &self.chunks[index])
+    }
+
+    /// Chunk at `coord`, marked dirty (see `mark_dirty`)
+    pub fn chunk_mut(&mut self, coord: ChunkCoord) -> Option<&mut S> {
+        let index = self.index_of(coord)?;
+        self.mark_dirty(coord);
+        Some(&mut self.chunks[index])
+    }
+
+    /// Block at `pos`, or `None` outside the world
+    pub fn get(&self, pos: WorldPosition) -> Option<Block<Id>> {
+        let (coord, block_pos) = pos.chunk_coord::<CHUNK_SIZE, CHUNK_WIDTH>()?;
+        Some(self.chunk(coord)?.get(block_pos))
+    }
+
+    /// Replace the block at `pos`, failing outside the world
+    pub fn set(&mut self, pos: WorldPosition, block: Block<Id>) -> Result<(), OutOfWorld> {
+        let (coord, block_pos) = pos
+            .chunk_coord::<CHUNK_SIZE, CHUNK_WIDTH>()
+            .ok_or(OutOfWorld(pos))?;
+        let index = self.index_of(coord).ok_or(OutOfWorld(pos))?;
+        self.chunks[index].set(block_pos, block);
+        for coord in affected_chunks(coord, block_pos) {
+            self.flag_dirty(coord);
+        }
+        Ok(())
+    }
+
+    /// Flag `coord` as modified, see `ChunkCoord::with_neighbours`
+    pub fn mark_dirty(&mut self, coord: ChunkCoord) {
+        for coord in coord.with_neighbours() {
+            self.flag_dirty(coord);
+        }
+    }
+
+    fn flag_dirty(&mut self, coord: ChunkCoord) {
+        if let Some(index) = self.index_of(coord) {
+            self.dirty[index] = true;
+        }
+    }
+
+    pub fn is_dirty(&self, coord: ChunkCoord) -> bool {
+        self.index_of(coord).is_some_and(|index| self.dirty[index])
+    }
+
+    /// Chunks modified since the previous call, clearing their flags
+    pub fn take_dirty(&mut self) -> Vec<ChunkCoord> {
+        let width = self.width;
+        let dirty = (0..self.dirty.len())
+            .filter(|&index| self.dirty[index])
+            .map(|index| coord_of(width, index))
+            .collect();
+        self.dirty.iter_mut().for_each(|dirty| *dirty = false);
+        dirty
+    }
+
+    /// Run `generator` on every chunk
+    pub fn generate(&mut self, generator: &dyn ChunkGenerator<Id, CHUNK_SIZE, CHUNK_WIDTH, S>) {
+        for (coord, chunk) in self {
+            generator.generate_chunk(coord, chunk);
+        }
+    }
+
+    /// Total memory held by the chunks, see `ChunkStorage::memory_usage`
+    pub fn memory_usage(&self) -> usize {
+        self.chunks.iter().map(ChunkStorage::memory_usage).sum()
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH> for DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Storage = S;
+
+    fn chunk(&self, coord: ChunkCoord) -> Option<&S> {
+        DynamicWorld::chunk(self, coord)
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > Index<ChunkCoord> for DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Output = S;
+
+    fn index(&self, index: ChunkCoord) -> &Self::Output {
+        self.chunk(index)
+            .unwrap_or_else(|| panic!("chunk {} outside the world", index))
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IndexMut<ChunkCoord> for DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    fn index_mut(&mut self, index: ChunkCoord) -> &mut Self::Output {
+        self.chunk_mut(index)
+            .unwrap_or_else(|| panic!("chunk {} outside the world", index))
+    }
+}
+
+fn coord_of(width: usize, index: usize) -> ChunkCoord {
+    ChunkCoord::new((index % width) as i32, (index / width) as i32)
+}
+
+pub struct DynamicWorldIter<'world, S> {
+    width: usize,
+    chunks: std::iter::Enumerate<std::slice::Iter<'world, S>>,
+}
+
+impl<'world, S> Iterator for DynamicWorldIter<'world, S> {
+    type Item = (ChunkCoord, &'world S);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let width = self.width;
+        self.chunks
+            .next()
+            .map(|(index, chunk)| (coord_of(width, index), chunk))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+pub struct DynamicWorldIterMut<'world, S> {
+    width: usize,
+    chunks: std::iter::Enumerate<std::slice::IterMut<'world, S>>,
+}
+
+impl<'world, S> Iterator for DynamicWorldIterMut<'world, S> {
+    type Item = (ChunkCoord, &'world mut S);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let width = self.width;
+        self.chunks
+            .next()
+            .map(|(index, chunk)| (coord_of(width, index), chunk))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+impl<
+        'world,
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IntoIterator for &'world DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Item = (ChunkCoord, &'world S);
+
+    type IntoIter = DynamicWorldIter<'world, S>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        DynamicWorldIter {
+            width: self.width,
+            chunks: self.chunks.iter().enumerate(),
+        }
+    }
+}
+
+impl<
+        'world,
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IntoIterator for &'world mut DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Item = (ChunkCoord, &'world mut S);
+
+    type IntoIter = DynamicWorldIterMut<'world, S>;
+
+    /// Flags every chunk dirty, whether or not it is written through the iterator
+    fn into_iter(self) -> Self::IntoIter {
+        self.dirty.iter_mut().for_each(|dirty| *dirty = true);
+        DynamicWorldIterMut {
+            width: self.width,
+            chunks: self.chunks.iter_mut().enumerate(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generator::{flat::Flat, WorldGenerator},
+        mesher::{culling::Culling, Mesher},
+        packs::{basic::*, Pack},
+        World,
+    };
+
+    use super::*;
+
+    #[test]
+    fn meshes_like_const_world() {
+        let generator = Flat::new_simple(5);
+        let mut fixed = World::<BasicId, 4, 2, 4096, 16>::create();
+        generator.generate(fixed.as_mut());
+        let mut dynamic = DynamicWorld::<BasicId, 4096, 16>::new(2, 2);
+        dynamic.generate(&generator);
+        assert!(dynamic.chunk(ChunkCoord::new(2, 0)).is_none());
+        for (coord, _) in &dynamic {
+            assert_eq!(
+                Culling.mesh(&dynamic, BasicPack::get_map(), coord),
+                Culling.mesh(fixed.as_ref(), BasicPack::get_map(), coord)
+            );
+        }
+    }
+
+    #[test]
+    fn tracks_dirty_chunks() {
+        let mut world = DynamicWorld::<BasicId, 4096, 16>::new(3, 2);
+        assert_eq!(world.take_dirty().len(), 6);
+        assert!(world.take_dirty().is_empty());
+        let stone = Block::solid(BasicId::Stone);
+        world.set(WorldPosition::new(20, 0, 5), stone).unwrap();
+        assert_eq!(world.take_dirty(), vec![ChunkCoord::new(1, 0)]);
+        world.set(WorldPosition::new(31, 0, 15), stone).unwrap();
+        assert_eq!(
+            world.take_dirty(),
+            vec![
+                ChunkCoord::new(1, 0),
+                ChunkCoord::new(2, 0),
+                ChunkCoord::new(1, 1)
+            ]
+        );
+        assert_eq!(world.get(WorldPosition::new(31, 0, 15)), Some(stone));
+        let outside = WorldPosition::new(-1, 0, 0);
+        assert_eq!(world.set(outside, stone), Err(OutOfWorld(outside)));
+        world.chunk_mut(ChunkCoord::new(0, 1));
+        assert_eq!(world.take_dirty().len(), 3);
+    }
+}
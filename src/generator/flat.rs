@@ -1,6 +1,9 @@
-use crate::{packs::SimpleBlockId, *};
+use crate::{
+    packs::{SimpleBlockId, SimpleBlocks},
+    *,
+};
 
-use super::WorldGenerator;
+use super::ChunkGenerator;
 
 #[derive(Debug)]
 pub struct Flat<Id: BlockId> {
@@ -10,7 +13,7 @@ pub struct Flat<Id: BlockId> {
 impl<Id: BlockId> Flat<Id> {
     fn get_block(&self, level: u16) -> Block<Id> {
         if let Some(&Some(id)) = self.receipe.get(level as usize) {
-            Block::Solid { id }
+            Block::solid(id)
         } else {
             Block::Empty
         }
@@ -19,33 +22,37 @@ impl<Id: BlockId> Flat<Id> {
     pub fn new(receipe: Vec<Option<Id>>) -> Self {
         Self { receipe }
     }
-}
 
-impl<Id: SimpleBlockId> Flat<Id> {
-    pub fn new_simple(level: usize) -> Self {
+    /// `level` blocks high, the simple block topped by the top one
+    pub fn from_blocks(blocks: &SimpleBlocks<Id>, level: usize) -> Self {
         Self {
-            receipe: std::iter::repeat(Some(Id::get_simple_block()))
+            receipe: std::iter::repeat(Some(blocks.simple))
                 .take(level - 1)
-                .chain(std::iter::once(Some(Id::get_simple_top_block())))
+                .chain(std::iter::once(Some(blocks.top)))
                 .collect(),
         }
     }
 }
 
+impl<Id: SimpleBlockId> Flat<Id> {
+    pub fn new_simple(level: usize) -> Self {
+        Self::from_blocks(&SimpleBlocks::of(), level)
+    }
+}
+
+impl<Id: BlockId> ClimateSource for Flat<Id> {}
+
 impl<
         Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
         const CHUNK_SIZE: usize,
         const CHUNK_WIDTH: usize,
-    > WorldGenerator<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH> for Flat<Id>
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > ChunkGenerator<Id, CHUNK_SIZE, CHUNK_WIDTH, S> for Flat<Id>
 {
-    fn generate(&self, world: &mut World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>) {
-        for (_, chunk) in world {
-            for (pos, block) in chunk {
-                let (_, level, _) = pos.into();
-                *block = self.get_block(level);
-            }
-        }
+    fn generate_chunk(&self, _coord: ChunkCoord, chunk: &mut S) {
+        chunk.fill(|pos| {
+            let (_, level, _) = pos.into();
+            self.get_block(level)
+        });
     }
 }
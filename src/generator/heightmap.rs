@@ -0,0 +1,271 @@
+use std::f32::consts::{SQRT_2, TAU};
+
+use crate::{
+    packs::{basic::BasicId, SimpleBlockId, SimpleBlocks},
+    *,
+};
+
+use super::{mix_seed, ChunkGenerator};
+
+/// Layered 2D gradient noise, each octave at twice the frequency and half the
+/// amplitude of the one before
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Octaves {
+    pub octaves: u32,
+    /// Frequency of the first octave, in waves per block
+    pub frequency: f32,
+    /// Largest height change of the first octave, in blocks
+    pub amplitude: f32,
+}
+
+impl Default for Octaves {
+    fn default() -> Self {
+        Self {
+            octaves: 4,
+            frequency: 1.0 / 64.0,
+            amplitude: 8.0,
+        }
+    }
+}
+
+impl Octaves {
+    /// Noise at world block coordinates `(x, z)`, within about
+    /// `-2 * amplitude..2 * amplitude`
+    pub fn sample(&self, seed: u64, x: i32, z: i32) -> f32 {
+        let (mut frequency, mut amplitude) = (self.frequency, self.amplitude);
+        let mut height = 0.0;
+        for octave in 0..self.octaves {
+            let seed = mix_seed(seed, &[octave as u64]);
+            height += gradient_noise(seed, x as f32 * frequency, z as f32 * frequency) * amplitude;
+            frequency *= 2.0;
+            amplitude *= 0.5;
+        }
+        height
+    }
+}
+
+/// Natural looking terrain: a noise heightmap filled with stone, a few layers of
+/// dirt and a top block picked by height
+#[derive(Debug, Clone)]
+pub struct Heightmap<Id: BlockId> {
+    pub seed: u64,
+    pub noise: Octaves,
+    /// Height the noise varies around
+    pub base: u16,
+    pub stone: Id,
+    pub dirt: Id,
+    /// Dirt blocks between the stone and the top block
+    pub dirt_depth: u16,
+    /// Top blocks with the highest height they cover, lowest band first; the
+    /// last band also covers anything higher
+    pub tops: Vec<(u16, Id)>,
+}
+
+impl<Id: BlockId> Heightmap<Id> {
+    pub fn new(seed: u64, base: u16, stone: Id, dirt: Id, tops: Vec<(u16, Id)>) -> Self {
+        assert!(!tops.is_empty(), "terrain needs at least one top block");
+        Self {
+            seed,
+            noise: Octaves::default(),
+            base,
+            stone,
+            dirt,
+            dirt_depth: 3,
+            tops,
+        }
+    }
+
+    /// Terrain of the simple block topped by the top block
+    pub fn from_blocks(seed: u64, base: u16, blocks: &SimpleBlocks<Id>) -> Self {
+        Self::new(
+            seed,
+            base,
+            blocks.simple,
+            blocks.simple,
+            vec![(u16::MAX, blocks.top)],
+        )
+    }
+
+    pub fn with_noise(self, noise: Octaves) -> Self {
+        Self { noise, ..self }
+    }
+
+    /// Height of the top block of the column at world block coordinates `(x, z)`,
+    /// kept between 1 and `limit - 1`; chunks less than 2 blocks high only
+    /// have room for height 1, which lies above them when they are 1 high
+    pub fn height(&self, x: i32, z: i32, limit: u16) -> u16 {
+        let height = self.base as f32 + self.noise.sample(self.seed, x, z);
+        let highest = (limit as f32 - 1.0).max(1.0);
+        height.round().clamp(1.0, highest) as u16
+    }
+
+    /// Top block of a column `height` high
+    pub fn top(&self, height: u16) -> Id {
+        self.tops
+            .iter()
+            .find(|&&(limit, _)| height <= limit)
+            .unwrap_or(&self.tops[self.tops.len() - 1])
+            .1
+    }
+}
+
+impl<Id: SimpleBlockId> Heightmap<Id> {
+    /// Terrain of the simple block topped by the simple top block
+    pub fn new_simple(seed: u64, base: u16) -> Self {
+        Self::from_blocks(seed, base, &SimpleBlocks::of())
+    }
+}
+
+impl Heightmap<BasicId> {
+    /// Sand shores below `base`, grass up to 8 blocks above it and snow higher
+    pub fn basic(seed: u64, base: u16) -> Self {
+        Self::new(
+            seed,
+            base,
+            BasicId::Stone,
+            BasicId::Dirt,
+            vec![
+                (base.saturating_sub(1), BasicId::DirtSand),
+                (base.saturating_add(8), BasicId::DirtGrass),
+                (u16::MAX, BasicId::DirtSnow),
+            ],
+        )
+    }
+}
+
+impl<Id: BlockId> ClimateSource for Heightmap<Id> {
+    fn climate(&self, x: i32, z: i32) -> Climate {
+        Climate::at(self.seed, x, z)
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > ChunkGenerator<Id, CHUNK_SIZE, CHUNK_WIDTH, S> for Heightmap<Id>
+{
+    fn generate_chunk(&self, coord: ChunkCoord, chunk: &mut S) {
+        let limit = (CHUNK_SIZE / CHUNK_WIDTH / CHUNK_WIDTH) as u16;
+        let width = CHUNK_WIDTH as i32;
+        let heights: Vec<u16> = (0..width * width)
+            .map(|index| {
+                let (x, z) = (index % width, index / width);
+                self.height(coord.x * width + x, coord.z * width + z, limit)
+            })
+            .collect();
+        chunk.fill(|pos| {
+            let (x, y, z) = pos.into();
+            let height = heights[z as usize * CHUNK_WIDTH + x as usize];
+            if y > height {
+                Block::Empty
+            } else if y == height {
+                Block::solid(self.top(height))
+            } else if y >= height.saturating_sub(self.dirt_depth) {
+                Block::solid(self.dirt)
+            } else {
+                Block::solid(self.stone)
+            }
+        });
+    }
+}
+
+/// Lattice hash of `(x, z)`, salted by `seed`
+fn lattice_hash(seed: u64, x: i32, z: i32) -> u32 {
+    mix_seed(seed, &[x as u32 as u64, z as u32 as u64]) as u32
+}
+
+/// Perlin style gradient noise, within about `-1.0..1.0`
+fn gradient_noise(seed: u64, x: f32, z: f32) -> f32 {
+    let (x0, z0) = (x.floor(), z.floor());
+    let (fx, fz) = (x - x0, z - z0);
+    let (cx, cz) = (x0 as i32, z0 as i32);
+    // Dot product of a random unit gradient at a corner with the offset to it
+    let corner = |dx: i32, dz: i32| {
+        let angle = lattice_hash(seed, cx + dx, cz + dz) as f32 / u32::MAX as f32 * TAU;
+        angle.cos() * (fx - dx as f32) + angle.sin() * (fz - dz as f32)
+    };
+    let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let (u, v) = (fade(fx), fade(fz));
+    let near = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * u;
+    let far = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * u;
+    (near + (far - near) * v) * SQRT_2
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::WorldGenerator;
+
+    use super::*;
+
+    type TestWorld = World<BasicId, 4, 2, 4096, 16>;
+
+    #[test]
+    fn terrain_is_smooth_and_banded() {
+        let terrain = Heightmap::basic(5, 8).with_noise(Octaves {
+            octaves: 3,
+            frequency: 1.0 / 16.0,
+            amplitude: 4.0,
+        });
+        assert_eq!(terrain.top(7), BasicId::DirtSand);
+        assert_eq!(terrain.top(16), BasicId::DirtGrass);
+        assert_eq!(terrain.top(17), BasicId::DirtSnow);
+
+        let mut world = TestWorld::create();
+        terrain.generate(world.as_mut());
+        let mut heights = Vec::new();
+        for x in 0..32 {
+            for z in 0..32 {
+                let height = terrain.height(x, z, 16);
+                heights.push(height);
+                for y in 0..16 {
+                    let expected = match y {
+                        y if y > height => Block::Empty,
+                        y if y == height => Block::solid(terrain.top(height)),
+                        y if y + 3 >= height => Block::solid(BasicId::Dirt),
+                        _ => Block::solid(BasicId::Stone),
+                    };
+                    assert_eq!(world.get(WorldPosition::new(x, y, z)), Some(expected));
+                }
+            }
+        }
+        assert!(heights.iter().min() < heights.iter().max());
+        // Neighbouring columns differ by a block or two, across chunks too
+        for x in -40..40 {
+            let step = terrain.height(x, 3, 16) as i32 - terrain.height(x + 1, 3, 16) as i32;
+            assert!(step.abs() <= 2, "{} at x {}", step, x);
+        }
+
+        let mut again = TestWorld::create();
+        terrain.generate(again.as_mut());
+        assert_eq!(world.content_hash(), again.content_hash());
+    }
+
+    #[test]
+    fn handles_extreme_sizes() {
+        let terrain = Heightmap::basic(5, u16::MAX);
+        assert_eq!(terrain.top(u16::MAX), BasicId::DirtGrass);
+        for limit in 0..3 {
+            assert_eq!(terrain.height(0, 0, limit), 1);
+        }
+        // Chunks a single block high
+        let mut flat = World::<BasicId, 4, 2, 16, 4>::create();
+        Heightmap::basic(5, 8).generate(flat.as_mut());
+        assert_eq!(
+            flat.get(WorldPosition::new(1, 0, 1)),
+            Some(Block::solid(BasicId::Dirt))
+        );
+        // Dirt all the way down
+        let mut deep = TestWorld::create();
+        let terrain = Heightmap {
+            dirt_depth: u16::MAX,
+            ..Heightmap::basic(5, 8)
+        };
+        terrain.generate(deep.as_mut());
+        assert_eq!(
+            deep.get(WorldPosition::new(0, 0, 0)),
+            Some(Block::solid(BasicId::Dirt))
+        );
+    }
+}
@@ -1,15 +1,114 @@
-use crate::{BlockId, World};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::{BlockId, Chunk, ChunkCoord, ChunkStorage, ClimateSource, World};
 
 pub mod flat;
+pub mod heightmap;
 pub mod random;
 
+/// Fills a whole world; every generator also tells the climate of its columns
 pub trait WorldGenerator<
     Id: BlockId,
     const SIZE: usize,
     const WIDTH: usize,
     const CHUNK_SIZE: usize,
     const CHUNK_WIDTH: usize,
->
+    S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> = Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>: ClimateSource
+{
+    fn generate(&self, world: &mut World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>);
+}
+
+/// `seed` hashed together with `words`, chaining the SplitMix64 finalizer so
+/// neighbouring inputs give unrelated outputs
+pub fn mix_seed(seed: u64, words: &[u64]) -> u64 {
+    words
+        .iter()
+        .fold(splitmix64(seed), |hash, &word| splitmix64(hash ^ word))
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Random number generator for the chunk at `coord` of a world generated from
+/// `seed`
+///
+/// Seeding per chunk keeps every chunk the same whatever order chunks are
+/// generated in, and on any world layout. The generator is ChaCha8, whose
+/// output is fixed on every platform and `rand_chacha` release.
+pub fn chunk_rng(seed: u64, coord: ChunkCoord) -> ChaCha8Rng {
+    ChaCha8Rng::seed_from_u64(mix_seed(
+        seed,
+        &[coord.x as u32 as u64, coord.z as u32 as u64],
+    ))
+}
+
+/// Generator filling one chunk at a time, usable on any world layout
+pub trait ChunkGenerator<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> = Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>: ClimateSource
+{
+    fn generate_chunk(&self, coord: ChunkCoord, chunk: &mut S);
+}
+
+impl<
+        G: ChunkGenerator<Id, CHUNK_SIZE, CHUNK_WIDTH, S>,
+        Id: BlockId,
+        const SIZE: usize,
+        const WIDTH: usize,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > WorldGenerator<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S> for G
 {
-    fn generate(&self, world: &mut World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>);
+    fn generate(&self, world: &mut World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>) {
+        for (pos, chunk) in world {
+            self.generate_chunk(pos.into(), chunk);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::Rng;
+
+    use super::*;
+
+    #[test]
+    fn chunk_rngs_differ() {
+        // Seeds and positions must not cancel out, as they can when XORed
+        let mut seen = HashSet::new();
+        for seed in 0..4 {
+            for x in -4..4 {
+                for z in -4..4 {
+                    let value: u64 = chunk_rng(seed, ChunkCoord::new(x, z)).gen();
+                    assert!(seen.insert(value), "seed {} at {}, {}", seed, x, z);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunk_rng_is_pinned() {
+        let mut rng = chunk_rng(42, ChunkCoord::new(-3, 7));
+        let values: [u64; 3] = [rng.gen(), rng.gen(), rng.gen()];
+        assert_eq!(
+            values,
+            [
+                9_274_469_380_555_946_161,
+                3_838_209_678_388_993_761,
+                13_963_192_488_675_654_351,
+            ]
+        );
+    }
 }
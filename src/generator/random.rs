@@ -1,50 +1,95 @@
-use crate::{packs::SimpleBlockId, Block};
+use rand::Rng;
 
-use super::WorldGenerator;
+use crate::{
+    packs::{SimpleBlockId, SimpleBlocks},
+    Block, BlockId, ChunkCoord, ChunkStorage, Climate, ClimateSource,
+};
 
-#[derive(Debug)]
-pub enum RandomGenerator {
+use super::{chunk_rng, ChunkGenerator};
+
+/// Which blocks `RandomGenerator` fills
+#[derive(Debug, Clone, Copy)]
+pub enum RandomFill {
     Fill,
     Odd,
     FillRate(f32),
 }
 
+/// Random blocks, the same for the same `seed` (see `chunk_rng`)
+#[derive(Debug)]
+pub struct RandomGenerator<Id: BlockId> {
+    pub fill: RandomFill,
+    pub seed: u64,
+    /// Blocks filled in, see `SimpleBlocks::random`
+    pub blocks: SimpleBlocks<Id>,
+}
+
+impl<Id: BlockId> RandomGenerator<Id> {
+    pub fn with_blocks(fill: RandomFill, seed: u64, blocks: SimpleBlocks<Id>) -> Self {
+        Self { fill, seed, blocks }
+    }
+}
+
+impl<Id: SimpleBlockId> RandomGenerator<Id> {
+    pub fn new(fill: RandomFill, seed: u64) -> Self {
+        Self::with_blocks(fill, seed, SimpleBlocks::of())
+    }
+}
+
+impl<Id: BlockId> ClimateSource for RandomGenerator<Id> {
+    fn climate(&self, x: i32, z: i32) -> Climate {
+        Climate::at(self.seed, x, z)
+    }
+}
+
 impl<
-        Id: SimpleBlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
+        Id: BlockId,
         const CHUNK_SIZE: usize,
         const CHUNK_WIDTH: usize,
-    > WorldGenerator<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH> for RandomGenerator
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > ChunkGenerator<Id, CHUNK_SIZE, CHUNK_WIDTH, S> for RandomGenerator<Id>
 {
-    fn generate(&self, world: &mut crate::World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>) {
-        for (_, chunk) in world {
-            for (pos, block) in chunk {
-                *block = match self {
-                    RandomGenerator::Fill => Block::Solid {
-                        id: Id::get_random_block(),
-                    },
-                    RandomGenerator::Odd => {
-                        let (x, y, z) = pos.into();
-                        if (x ^ y ^ z) % 2 == 0 {
-                            Block::Solid {
-                                id: Id::get_random_block(),
-                            }
-                        } else {
-                            Block::Empty
-                        }
-                    }
-                    RandomGenerator::FillRate(rate) => {
-                        if rand::random::<f32>() <= *rate {
-                            Block::Solid {
-                                id: Id::get_random_block(),
-                            }
-                        } else {
-                            Block::Empty
-                        }
-                    }
+    fn generate_chunk(&self, coord: ChunkCoord, chunk: &mut S) {
+        let mut rng = chunk_rng(self.seed, coord);
+        chunk.fill(|pos| match self.fill {
+            RandomFill::Fill => Block::solid(self.blocks.random(&mut rng)),
+            RandomFill::Odd => {
+                let (x, y, z) = pos.into();
+                if (x ^ y ^ z) % 2 == 0 {
+                    Block::solid(self.blocks.random(&mut rng))
+                } else {
+                    Block::Empty
                 }
             }
+            RandomFill::FillRate(rate) => {
+                if rng.gen::<f32>() <= rate {
+                    Block::solid(self.blocks.random(&mut rng))
+                } else {
+                    Block::Empty
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generator::WorldGenerator,
+        packs::{basic::*, Pack},
+        BlockKind, World,
+    };
+
+    use super::*;
+
+    #[test]
+    fn fills_with_opaque_blocks() {
+        let mut world = World::<BasicId, 4, 2, 4096, 16>::create();
+        RandomGenerator::new(RandomFill::Fill, 7).generate(world.as_mut());
+        for (_, chunk) in world.as_ref() {
+            for (_, id) in chunk.iter_solid() {
+                assert_eq!(BasicPack::get_map()[id].kind(), BlockKind::Opaque, "{}", id);
+            }
         }
     }
 }
@@ -0,0 +1,275 @@
+use std::{
+    collections::{btree_map, BTreeMap},
+    fmt::Display,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+use crate::{generator::ChunkGenerator, BlockId, Chunk, ChunkCoord, ChunkSource, ChunkStorage};
+
+/// Unbounded world keyed by signed chunk coordinates, chunks are created on demand
+///
+/// Chunks iterate in `ChunkCoord` order, by `x` and then `z`, the same on every
+/// run. Indexing a chunk that does not exist panics, see `get_or_create`.
+#[derive(Debug, Clone)]
+pub struct InfiniteWorld<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> = Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+> {
+    chunks: BTreeMap<ChunkCoord, S>,
+    phat: PhantomData<Id>,
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > Default for InfiniteWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    fn default() -> Self {
+        Self {
+            chunks: BTreeMap::new(),
+            phat: PhantomData,
+        }
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> + Display,
+    > Display for InfiniteWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "InfiniteWorld<{}, {}, {}>",
+            std::any::type_name::<Id>(),
+            CHUNK_SIZE,
+            CHUNK_WIDTH
+        )?;
+        for (pos, chunk) in self {
+            writeln!(f, "{}: {}", pos, chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > InfiniteWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn contains(&self, coord: ChunkCoord) -> bool {
+        self.chunks.contains_key(&coord)
+    }
+
+    pub fn chunk(&self, coord: ChunkCoord) -> Option<&S> {
+        self.chunks.get(&coord)
+    }
+
+    pub fn chunk_mut(&mut self, coord: ChunkCoord) -> Option<&mut S> {
+        self.chunks.get_mut(&coord)
+    }
+
+    /// Chunk at `coord`, inserting an empty one if it does not exist yet
+    pub fn get_or_create(&mut self, coord: ChunkCoord) -> &mut S {
+        self.chunks.entry(coord).or_default()
+    }
+
+    /// Chunk at `coord`, running `generator` on it first if it does not exist yet
+    pub fn get_or_generate(
+        &mut self,
+        coord: ChunkCoord,
+        generator: &dyn ChunkGenerator<Id, CHUNK_SIZE, CHUNK_WIDTH, S>,
+    ) -> &mut S {
+        self.chunks.entry(coord).or_insert_with(|| {
+            let mut chunk = S::default();
+            generator.generate_chunk(coord, &mut chunk);
+            chunk
+        })
+    }
+
+    /// Make sure every chunk in the inclusive rectangle `from..=to` exists
+    pub fn generate_area(
+        &mut self,
+        from: ChunkCoord,
+        to: ChunkCoord,
+        generator: &dyn ChunkGenerator<Id, CHUNK_SIZE, CHUNK_WIDTH, S>,
+    ) {
+        for z in from.z..=to.z {
+            for x in from.x..=to.x {
+                self.get_or_generate(ChunkCoord::new(x, z), generator);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, coord: ChunkCoord) -> Option<S> {
+        self.chunks.remove(&coord)
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > Index<ChunkCoord> for InfiniteWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Output = S;
+
+    fn index(&self, index: ChunkCoord) -> &Self::Output {
+        self.chunk(index)
+            .unwrap_or_else(|| panic!("no chunk at {}", index))
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IndexMut<ChunkCoord> for InfiniteWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    fn index_mut(&mut self, index: ChunkCoord) -> &mut Self::Output {
+        self.chunk_mut(index)
+            .unwrap_or_else(|| panic!("no chunk at {}", index))
+    }
+}
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH> for InfiniteWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Storage = S;
+
+    fn chunk(&self, coord: ChunkCoord) -> Option<&S> {
+        InfiniteWorld::chunk(self, coord)
+    }
+}
+
+type ChunkEntry<'world, S> = (&'world ChunkCoord, &'world S);
+type ChunkEntryMut<'world, S> = (&'world ChunkCoord, &'world mut S);
+
+impl<
+        'world,
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IntoIterator for &'world InfiniteWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Item = (ChunkCoord, &'world S);
+
+    type IntoIter = std::iter::Map<
+        btree_map::Iter<'world, ChunkCoord, S>,
+        fn(ChunkEntry<'world, S>) -> (ChunkCoord, &'world S),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.iter().map(|(&coord, chunk)| (coord, chunk))
+    }
+}
+
+impl<
+        'world,
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IntoIterator for &'world mut InfiniteWorld<Id, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Item = (ChunkCoord, &'world mut S);
+
+    type IntoIter = std::iter::Map<
+        btree_map::IterMut<'world, ChunkCoord, S>,
+        fn(ChunkEntryMut<'world, S>) -> (ChunkCoord, &'world mut S),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.iter_mut().map(|(&coord, chunk)| (coord, chunk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generator::flat::Flat,
+        mesher::{culling::Culling, greedy::Greedy, Face, Mesher},
+        packs::{basic::*, Pack},
+        BlockFace, BlockSubPos,
+    };
+
+    use super::*;
+
+    #[test]
+    fn generates_chunks_on_demand() {
+        let mut world = InfiniteWorld::<BasicId, 4096, 16>::new();
+        let generator = Flat::new_simple(3);
+        world.generate_area(ChunkCoord::new(-2, -1), ChunkCoord::new(1, 1), &generator);
+        assert_eq!(world.len(), 12);
+        assert!(world.contains(ChunkCoord::new(-2, -1)));
+        assert!(!world.contains(ChunkCoord::new(2, 0)));
+        for (_, chunk) in &world {
+            assert_eq!(chunk.iter_solid().count(), 3 * 16 * 16);
+        }
+        let coords: Vec<_> = world.into_iter().map(|(coord, _)| coord).collect();
+        assert!(coords.windows(2).all(|pair| pair[0] < pair[1]));
+        world.get_or_create(ChunkCoord::new(5, -7))[BlockSubPos::new(0, 0, 0)] =
+            crate::Block::Empty;
+        assert_eq!(world.len(), 13);
+    }
+
+    #[test]
+    #[should_panic(expected = "no chunk at")]
+    fn index_mut_does_not_create() {
+        let mut world = InfiniteWorld::<BasicId, 4096, 16>::new();
+        world[ChunkCoord::new(0, 0)][BlockSubPos::new(0, 0, 0)] = crate::Block::Empty;
+    }
+
+    #[test]
+    fn meshes_across_the_origin() {
+        type TestWorld = InfiniteWorld<BasicId, 4096, 16>;
+        let mut world = TestWorld::new();
+        let (left, right) = (ChunkCoord::new(-1, 0), ChunkCoord::new(0, 0));
+        world.generate_area(left, right, &Flat::new_simple(3));
+        let meshers: [&dyn Mesher<BasicId, 4096, 16, TestWorld>; 2] = [&Culling, &Greedy];
+        for mesher in meshers {
+            let faces = |coord| mesher.faces(&world, BasicPack::get_map(), coord);
+            let (left_faces, right_faces) = (faces(left), faces(right));
+            assert!(!left_faces.is_empty());
+            assert!(left_faces
+                .iter()
+                .all(|face| (-16..0).contains(&face.position.x)));
+            assert!(right_faces
+                .iter()
+                .all(|face| (0..16).contains(&face.position.x)));
+            // The shared border is hidden from both sides
+            assert!(!left_faces.iter().any(|face| face.face == BlockFace::East));
+            assert!(!right_faces.iter().any(|face| face.face == BlockFace::West));
+            let area = |faces: &[Face]| faces.iter().map(|face| face.area()).sum::<usize>();
+            assert_eq!(area(&left_faces), area(&right_faces));
+        }
+    }
+}
@@ -1,3 +1,4 @@
+pub mod biome;
 pub mod block;
 pub mod chunk;
 pub mod renderer;
@@ -6,11 +7,23 @@ pub mod assets;
 pub mod world;
 pub mod utils;
 pub mod generator;
+pub mod dynamic;
+pub mod infinite;
+pub mod mesher;
+pub mod octree;
 pub mod packs;
+pub mod palette;
+pub mod registry;
 pub mod camera;
 
+pub use biome::*;
 pub use block::*;
 pub use chunk::*;
+pub use dynamic::*;
+pub use infinite::*;
+pub use octree::*;
+pub use palette::*;
+pub use registry::*;
 pub use renderer::*;
 pub use texture::*;
 pub use world::*;
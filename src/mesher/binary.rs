@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+
+use enum_map::EnumMap;
+
+use crate::{
+    Block, BlockFace, BlockId, BlockKind, BlockSubPos, ChunkCoord, ChunkSource, ChunkStorage,
+    SolidBlockDefinition, TextureIndex, TextureInfo, TextureTransform, Tint,
+};
+
+use super::{
+    culling::{block_faces, face_texture},
+    greedy::{greedy_faces, slice_extent, slice_to_local},
+    Face, FaceShape, Mesher,
+};
+
+/// Face pairs sharing a normal axis, as `(towards lower, towards higher)`
+const AXES: [(BlockFace, BlockFace); 3] = [
+    (BlockFace::North, BlockFace::South),
+    (BlockFace::West, BlockFace::East),
+    (BlockFace::Down, BlockFace::Up),
+];
+
+/// Merge the set bits of `rows` (one `u64` per v, bit index is u) into rectangles,
+/// consuming them; `emit` receives `(u, v, size)`
+fn merge_rows(rows: &mut [u64], mut emit: impl FnMut(usize, usize, (u16, u16))) {
+    let max = TextureInfo::MAX_REPEAT as u32;
+    for v in 0..rows.len() {
+        while rows[v] != 0 {
+            let u = rows[v].trailing_zeros();
+            let w = (rows[v] >> u).trailing_ones().min(max);
+            let run = (u64::MAX >> (64 - w)) << u;
+            let mut h = 1;
+            while v + h < rows.len() && (h as u32) < max && rows[v + h] & run == run {
+                rows[v + h] &= !run;
+                h += 1;
+            }
+            rows[v] &= !run;
+            emit(u as usize, v, (w as u16, h as u16));
+        }
+    }
+}
+
+fn is_opaque<Id: BlockId>(
+    definitions: &EnumMap<Id, SolidBlockDefinition>,
+    block: Block<Id>,
+) -> bool {
+    matches!(block, Block::Solid { id, .. } if definitions[id].kind() == BlockKind::Opaque)
+}
+
+/// Greedy mesher working on per-column occupancy bitmasks
+///
+/// For every face axis each `(u, v)` column of the chunk is packed into a `u64`,
+/// padded with one bit from each adjacent chunk, so exposed faces fall out of a
+/// shift and a mask; the exposed bits are then regrouped into per-slice,
+/// per-texture rows and merged with bit runs. Only opaque blocks go through the
+/// bitmasks, every other kind is meshed per block.
+///
+/// Chunks more than 62 blocks wide or tall leave no room for the padding bits
+/// and are meshed by `greedy_faces` instead.
+pub fn binary_greedy_faces<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>(
+    world: &W,
+    definitions: &EnumMap<Id, SolidBlockDefinition>,
+    coord: ChunkCoord,
+) -> Vec<Face> {
+    let height = BlockSubPos::<CHUNK_SIZE, CHUNK_WIDTH>::HEIGHT;
+    if CHUNK_WIDTH + 2 > 64 || height + 2 > 64 {
+        return greedy_faces(world, definitions, coord);
+    }
+    let chunk = match world.chunk(coord) {
+        Some(chunk) => chunk,
+        None => return Vec::new(),
+    };
+    let mut faces = Vec::new();
+    for &(lower, upper) in &AXES {
+        let (n_len, u_len, v_len) = slice_extent(lower, CHUNK_WIDTH, height);
+        let inner = (u64::MAX >> (64 - n_len)) << 1;
+        let mut columns = vec![0u64; u_len * v_len];
+        for v in 0..v_len {
+            for u in 0..u_len {
+                let column = &mut columns[u + v * u_len];
+                for n in 0..n_len {
+                    let (x, y, z) = slice_to_local(lower, n, u, v);
+                    if is_opaque(definitions, chunk[BlockSubPos::new(x, y, z)]) {
+                        *column |= 1 << (n + 1);
+                    }
+                }
+                let (x, y, z) = slice_to_local(lower, 0, u, v);
+                if world
+                    .neighbour(coord, BlockSubPos::new(x, y, z), lower)
+                    .is_some_and(|block| is_opaque(definitions, block))
+                {
+                    *column |= 1;
+                }
+                let (x, y, z) = slice_to_local(upper, n_len - 1, u, v);
+                if world
+                    .neighbour(coord, BlockSubPos::new(x, y, z), upper)
+                    .is_some_and(|block| is_opaque(definitions, block))
+                {
+                    *column |= 1 << (n_len + 1);
+                }
+            }
+        }
+        for &face in &[lower, upper] {
+            // Ordered so faces come out the same on every run
+            let mut planes: BTreeMap<(usize, TextureIndex, TextureTransform, Tint), Vec<u64>> =
+                BTreeMap::new();
+            for v in 0..v_len {
+                for u in 0..u_len {
+                    let column = columns[u + v * u_len];
+                    let mut exposed = if face == lower {
+                        column & !(column << 1)
+                    } else {
+                        column & !(column >> 1)
+                    } & inner;
+                    while exposed != 0 {
+                        let n = exposed.trailing_zeros() as usize - 1;
+                        exposed &= exposed - 1;
+                        let (x, y, z) = slice_to_local(face, n, u, v);
+                        let block_pos = BlockSubPos::new(x, y, z);
+                        if let Block::Solid { id, state } = chunk[block_pos] {
+                            let definition = &definitions[id];
+                            let (texture, transform) = face_texture(
+                                world,
+                                definition,
+                                coord,
+                                block_pos,
+                                (id, state),
+                                face,
+                            );
+                            planes
+                                .entry((n, texture, transform, definition.tint(face)))
+                                .or_insert_with(|| vec![0; v_len])[v] |= 1 << u;
+                        }
+                    }
+                }
+            }
+            for ((n, texture, transform, tint), mut rows) in planes {
+                merge_rows(&mut rows, |u, v, size| {
+                    let (x, y, z) = slice_to_local(face, n, u, v);
+                    faces.push(Face {
+                        position: (coord, BlockSubPos::<CHUNK_SIZE, CHUNK_WIDTH>::new(x, y, z))
+                            .into(),
+                        face,
+                        texture,
+                        transform,
+                        tint,
+                        size,
+                        shape: FaceShape::Cube,
+                    });
+                });
+            }
+        }
+    }
+    for (block_pos, block) in chunk.iter_blocks() {
+        if let Block::Solid { id, state } = block {
+            if definitions[id].kind() != BlockKind::Opaque {
+                block_faces(
+                    world,
+                    definitions,
+                    coord,
+                    block_pos,
+                    (id, state),
+                    &mut faces,
+                );
+            }
+        }
+    }
+    faces
+}
+
+/// Greedy meshing on occupancy bitmasks
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BinaryGreedy;
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > Mesher<Id, CHUNK_SIZE, CHUNK_WIDTH, W> for BinaryGreedy
+{
+    fn faces(
+        &self,
+        world: &W,
+        definitions: &EnumMap<Id, SolidBlockDefinition>,
+        coord: ChunkCoord,
+    ) -> Vec<Face> {
+        binary_greedy_faces(world, definitions, coord)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generator::{
+            flat::Flat,
+            random::{RandomFill, RandomGenerator},
+            WorldGenerator,
+        },
+        mesher::{culling::Culling, greedy::Greedy},
+        packs::{basic::*, Pack},
+        World,
+    };
+
+    use super::*;
+
+    type TestWorld = World<BasicId, 4, 2, 4096, 16>;
+
+    fn total_area(world: &TestWorld, mesher: &dyn Mesher<BasicId, 4096, 16, TestWorld>) -> usize {
+        world
+            .into_iter()
+            .flat_map(|(pos, _)| mesher.faces(world, BasicPack::get_map(), pos.into()))
+            .map(|face| face.area())
+            .sum()
+    }
+
+    fn assert_same_surface(generator: impl WorldGenerator<BasicId, 4, 2, 4096, 16>) {
+        let mut world = TestWorld::create();
+        generator.generate(world.as_mut());
+        let culled = total_area(&world, &Culling);
+        assert!(culled > 0);
+        assert_eq!(total_area(&world, &Greedy), culled);
+        assert_eq!(total_area(&world, &BinaryGreedy), culled);
+    }
+
+    #[test]
+    fn flat_surface() {
+        assert_same_surface(Flat::new_simple(5));
+    }
+
+    #[test]
+    fn fill_surface() {
+        assert_same_surface(RandomGenerator::new(RandomFill::Fill, 1));
+    }
+
+    #[test]
+    fn odd_surface() {
+        assert_same_surface(RandomGenerator::new(RandomFill::Odd, 2));
+    }
+
+    #[test]
+    fn fill_rate_surface() {
+        assert_same_surface(RandomGenerator::new(RandomFill::FillRate(0.3), 3));
+    }
+
+    #[test]
+    fn faces_are_reproducible() {
+        let mut world = TestWorld::create();
+        RandomGenerator::new(RandomFill::FillRate(0.5), 5).generate(world.as_mut());
+        let faces =
+            || BinaryGreedy.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(1, 0));
+        let first = faces();
+        assert!(!first.is_empty());
+        assert_eq!(faces(), first);
+    }
+
+    #[test]
+    fn tall_chunks_fall_back() {
+        type TallWorld = World<BasicId, 1, 1, 1600, 4>;
+        let mut world = TallWorld::create();
+        RandomGenerator::new(RandomFill::FillRate(0.5), 4).generate(world.as_mut());
+        let definitions = BasicPack::get_map();
+        let area = |mesher: &dyn Mesher<BasicId, 1600, 4, TallWorld>| -> usize {
+            let faces = mesher.faces(&world, definitions, ChunkCoord::new(0, 0));
+            faces.iter().map(|face| face.area()).sum()
+        };
+        let culled = area(&Culling);
+        assert!(culled > 0);
+        assert_eq!(area(&BinaryGreedy), culled);
+    }
+}
@@ -0,0 +1,200 @@
+use enum_map::EnumMap;
+use strum::IntoEnumIterator;
+
+use crate::{
+    Block, BlockFace, BlockId, BlockKind, BlockState, BlockSubPos, ChunkCoord, ChunkSource,
+    ChunkStorage, SolidBlockDefinition, TextureIndex, TextureTransform,
+};
+
+use super::{Face, FaceShape, Mesher};
+
+/// Whether a cube face of a block `id` stays visible next to `neighbour`
+/// (`None` past the world edge)
+///
+/// Opaque neighbours hide everything, see-through cubes and liquids hide the
+/// faces of their own id, cross plants hide nothing.
+pub fn face_shows<Id: BlockId>(
+    definitions: &EnumMap<Id, SolidBlockDefinition>,
+    id: Id,
+    neighbour: Option<Block<Id>>,
+) -> bool {
+    match neighbour {
+        Some(Block::Solid { id: other, .. }) => match definitions[other].kind() {
+            BlockKind::Opaque => false,
+            BlockKind::Cross => true,
+            BlockKind::Transparent | BlockKind::Liquid => other != id,
+        },
+        _ => true,
+    }
+}
+
+/// Whether `face` of the block at `block_pos` is not covered by a neighbour,
+/// faces on the world edge count as visible
+pub fn is_face_visible<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>(
+    world: &W,
+    definitions: &EnumMap<Id, SolidBlockDefinition>,
+    coord: ChunkCoord,
+    block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
+    face: BlockFace,
+) -> bool {
+    match world.chunk(coord).map(|chunk| chunk.get(block_pos)) {
+        Some(Block::Solid { id, .. }) => {
+            face_shows(definitions, id, world.neighbour(coord, block_pos, face))
+        }
+        _ => false,
+    }
+}
+
+/// Texture and transform `face` of the block at `block_pos` shows, see
+/// `SolidBlockDefinition::texture_at`, with connected textures joined to the
+/// neighbours of the same block in the face plane
+pub fn face_texture<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>(
+    world: &W,
+    definition: &SolidBlockDefinition,
+    coord: ChunkCoord,
+    block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
+    (id, state): (Id, BlockState),
+    face: BlockFace,
+) -> (TextureIndex, TextureTransform) {
+    let (texture, transform) = definition.texture_at(state, face, (coord, block_pos).into());
+    let tiles = match definition.connected(texture) {
+        Some(tiles) => tiles,
+        None => return (texture, transform),
+    };
+    let mut mask = 0;
+    for (bit, &side) in face.plane_sides().iter().enumerate() {
+        if let Some(Block::Solid { id: other, .. }) = world.neighbour(coord, block_pos, side) {
+            if other == id {
+                mask |= 1 << bit;
+            }
+        }
+    }
+    (tiles[mask], transform)
+}
+
+/// Push the faces of one non-empty block, following the rules of its kind
+pub(crate) fn block_faces<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>(
+    world: &W,
+    definitions: &EnumMap<Id, SolidBlockDefinition>,
+    coord: ChunkCoord,
+    block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
+    (id, state): (Id, BlockState),
+    faces: &mut Vec<Face>,
+) {
+    let definition = &definitions[id];
+    let position = (coord, block_pos).into();
+    let shape = match definition.kind() {
+        BlockKind::Cross => {
+            for &face in &[
+                BlockFace::North,
+                BlockFace::South,
+                BlockFace::East,
+                BlockFace::West,
+            ] {
+                let (texture, transform) =
+                    face_texture(world, definition, coord, block_pos, (id, state), face);
+                faces.push(Face {
+                    position,
+                    face,
+                    texture,
+                    transform,
+                    tint: definition.tint(face),
+                    size: (1, 1),
+                    shape: FaceShape::Cross,
+                });
+            }
+            return;
+        }
+        BlockKind::Liquid => match world.neighbour(coord, block_pos, BlockFace::Up) {
+            Some(Block::Solid { id: above, .. })
+                if above == id || definitions[above].kind() == BlockKind::Opaque =>
+            {
+                FaceShape::Cube
+            }
+            _ => FaceShape::Lowered,
+        },
+        BlockKind::Opaque | BlockKind::Transparent => FaceShape::Cube,
+    };
+    for face in BlockFace::iter() {
+        if face_shows(definitions, id, world.neighbour(coord, block_pos, face)) {
+            let (texture, transform) =
+                face_texture(world, definition, coord, block_pos, (id, state), face);
+            faces.push(Face {
+                position,
+                face,
+                texture,
+                transform,
+                tint: definition.tint(face),
+                size: (1, 1),
+                shape,
+            });
+        }
+    }
+}
+
+/// Collect every visible face of the blocks in one chunk
+pub fn visible_faces<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>(
+    world: &W,
+    definitions: &EnumMap<Id, SolidBlockDefinition>,
+    coord: ChunkCoord,
+) -> Vec<Face> {
+    let mut faces = Vec::new();
+    let chunk = match world.chunk(coord) {
+        Some(chunk) => chunk,
+        None => return faces,
+    };
+    for (block_pos, block) in chunk.iter_blocks() {
+        if let Block::Solid { id, state } = block {
+            block_faces(
+                world,
+                definitions,
+                coord,
+                block_pos,
+                (id, state),
+                &mut faces,
+            );
+        }
+    }
+    faces
+}
+
+/// One quad per visible block face
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Culling;
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > Mesher<Id, CHUNK_SIZE, CHUNK_WIDTH, W> for Culling
+{
+    fn faces(
+        &self,
+        world: &W,
+        definitions: &EnumMap<Id, SolidBlockDefinition>,
+        coord: ChunkCoord,
+    ) -> Vec<Face> {
+        visible_faces(world, definitions, coord)
+    }
+}
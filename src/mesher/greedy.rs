@@ -0,0 +1,188 @@
+use enum_map::EnumMap;
+use strum::IntoEnumIterator;
+
+use crate::{
+    Block, BlockFace, BlockId, BlockKind, BlockSubPos, ChunkCoord, ChunkSource, ChunkStorage,
+    SolidBlockDefinition, TextureInfo,
+};
+
+use super::{
+    culling::{block_faces, face_shows, face_texture},
+    Face, FaceShape, Mesher,
+};
+
+/// Maps a slice coordinate `(normal, u, v)` of `face` back to a chunk-local `(x, y, z)`
+pub(crate) fn slice_to_local(face: BlockFace, n: usize, u: usize, v: usize) -> (u16, u16, u16) {
+    let (x, y, z) = match face {
+        BlockFace::North | BlockFace::South => (u, v, n),
+        BlockFace::East | BlockFace::West => (n, v, u),
+        BlockFace::Up | BlockFace::Down => (u, n, v),
+    };
+    (x as u16, y as u16, z as u16)
+}
+
+/// Extents `(normal, u, v)` of the slices swept for `face`
+pub(crate) fn slice_extent(face: BlockFace, width: usize, height: usize) -> (usize, usize, usize) {
+    match face {
+        BlockFace::North | BlockFace::South | BlockFace::East | BlockFace::West => {
+            (width, width, height)
+        }
+        BlockFace::Up | BlockFace::Down => (height, width, width),
+    }
+}
+
+/// Greedily cover every `Some` cell of a `u_len` x `v_len` mask with rectangles of
+/// equal looks (texture, transform and tint), consuming the mask; `emit` receives
+/// `(u, v, size, look)`
+fn merge_mask<T: Copy + PartialEq>(
+    mask: &mut [Option<T>],
+    u_len: usize,
+    v_len: usize,
+    mut emit: impl FnMut(usize, usize, (u16, u16), T),
+) {
+    let max = TextureInfo::MAX_REPEAT as usize;
+    for v in 0..v_len {
+        let mut u = 0;
+        while u < u_len {
+            let texture = match mask[u + v * u_len] {
+                Some(texture) => texture,
+                None => {
+                    u += 1;
+                    continue;
+                }
+            };
+            let mut w = 1;
+            while u + w < u_len && w < max && mask[u + w + v * u_len] == Some(texture) {
+                w += 1;
+            }
+            let mut h = 1;
+            while v + h < v_len
+                && h < max
+                && (u..u + w).all(|i| mask[i + (v + h) * u_len] == Some(texture))
+            {
+                h += 1;
+            }
+            for j in v..v + h {
+                for cell in &mut mask[u + j * u_len..u + w + j * u_len] {
+                    *cell = None;
+                }
+            }
+            emit(u, v, (w as u16, h as u16), texture);
+            u += w;
+        }
+    }
+}
+
+/// Whether faces of this kind are merged; liquids and plants are meshed per block
+fn merges(kind: BlockKind) -> bool {
+    matches!(kind, BlockKind::Opaque | BlockKind::Transparent)
+}
+
+/// Collect the visible faces of one chunk, merging adjacent coplanar faces
+/// sharing the same texture, transform and tint into larger quads
+pub fn greedy_faces<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>(
+    world: &W,
+    definitions: &EnumMap<Id, SolidBlockDefinition>,
+    coord: ChunkCoord,
+) -> Vec<Face> {
+    let chunk = match world.chunk(coord) {
+        Some(chunk) => chunk,
+        None => return Vec::new(),
+    };
+    let height = BlockSubPos::<CHUNK_SIZE, CHUNK_WIDTH>::HEIGHT;
+    let mut faces = Vec::new();
+    for face in BlockFace::iter() {
+        let (n_len, u_len, v_len) = slice_extent(face, CHUNK_WIDTH, height);
+        let mut mask = vec![None; u_len * v_len];
+        for n in 0..n_len {
+            for v in 0..v_len {
+                for u in 0..u_len {
+                    let (x, y, z) = slice_to_local(face, n, u, v);
+                    let block_pos = BlockSubPos::new(x, y, z);
+                    mask[u + v * u_len] = match chunk[block_pos] {
+                        Block::Solid { id, state }
+                            if merges(definitions[id].kind())
+                                && face_shows(
+                                    definitions,
+                                    id,
+                                    world.neighbour(coord, block_pos, face),
+                                ) =>
+                        {
+                            let definition = &definitions[id];
+                            let (texture, transform) = face_texture(
+                                world,
+                                definition,
+                                coord,
+                                block_pos,
+                                (id, state),
+                                face,
+                            );
+                            Some((texture, transform, definition.tint(face)))
+                        }
+                        _ => None,
+                    };
+                }
+            }
+            merge_mask(
+                &mut mask,
+                u_len,
+                v_len,
+                |u, v, size, (texture, transform, tint)| {
+                    let (x, y, z) = slice_to_local(face, n, u, v);
+                    faces.push(Face {
+                        position: (coord, BlockSubPos::<CHUNK_SIZE, CHUNK_WIDTH>::new(x, y, z))
+                            .into(),
+                        face,
+                        texture,
+                        transform,
+                        tint,
+                        size,
+                        shape: FaceShape::Cube,
+                    });
+                },
+            );
+        }
+    }
+    for (block_pos, block) in chunk.iter_blocks() {
+        match block {
+            Block::Solid { id, state } if !merges(definitions[id].kind()) => {
+                block_faces(
+                    world,
+                    definitions,
+                    coord,
+                    block_pos,
+                    (id, state),
+                    &mut faces,
+                );
+            }
+            _ => {}
+        }
+    }
+    faces
+}
+
+/// Merges coplanar faces sharing a texture
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Greedy;
+
+impl<
+        Id: BlockId,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > Mesher<Id, CHUNK_SIZE, CHUNK_WIDTH, W> for Greedy
+{
+    fn faces(
+        &self,
+        world: &W,
+        definitions: &EnumMap<Id, SolidBlockDefinition>,
+        coord: ChunkCoord,
+    ) -> Vec<Face> {
+        greedy_faces(world, definitions, coord)
+    }
+}
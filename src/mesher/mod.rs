@@ -0,0 +1,605 @@
+use std::iter::FromIterator;
+
+use enum_map::EnumMap;
+
+use crate::{
+    BlockFace, BlockId, ChunkCoord, ChunkSource, Climate, ClimateSource, SolidBlockDefinition,
+    TextureIndex, TextureInfo, TextureTransform, Tint, WorldPosition,
+};
+
+pub mod binary;
+pub mod culling;
+pub mod greedy;
+
+/// Geometry a `Face` describes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaceShape {
+    /// Axis-aligned face of a cube
+    #[default]
+    Cube,
+    /// Cube face whose upper edge sits `LIQUID_DROP` lower, used for liquids
+    /// with an exposed surface
+    Lowered,
+    /// Diagonal quad of a cross plant, see `Face::corners`
+    Cross,
+}
+
+/// How far the exposed surface of a liquid sits below the block top
+pub const LIQUID_DROP: f32 = 0.125;
+
+/// One visible quad, ready to be uploaded by a renderer
+///
+/// `size` is the number of blocks the quad spans along the texture's u and v
+/// axes; per-block faces are `(1, 1)`, merged faces are larger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Face {
+    pub position: WorldPosition,
+    pub face: BlockFace,
+    pub texture: TextureIndex,
+    pub transform: TextureTransform,
+    pub tint: Tint,
+    pub size: (u16, u16),
+    pub shape: FaceShape,
+}
+
+impl Face {
+    /// Quad corners in counter-clockwise order, matching `TextureIndex::into_arr`
+    ///
+    /// Cross quads span a block diagonally: `West`/`East` are the two sides of
+    /// the quad through the `(0, 0)`-`(1, 1)` corners, `North`/`South` the two
+    /// sides of the one through `(1, 0)`-`(0, 1)`.
+    pub fn corners(&self) -> [WorldPosition; 4] {
+        let origin = self.position;
+        if self.shape == FaceShape::Cross {
+            let (left, right) = match self.face {
+                BlockFace::West => (origin, origin.shift(1, 0, 1)),
+                BlockFace::East => (origin.shift(1, 0, 1), origin),
+                BlockFace::North => (origin.shift(1, 0, 0), origin.shift(0, 0, 1)),
+                _ => (origin.shift(0, 0, 1), origin.shift(1, 0, 0)),
+            };
+            return [left.shift(0, 1, 0), left, right, right.shift(0, 1, 0)];
+        }
+        let (u, v) = self.size;
+        let (uw, vw) = (u as i32, v as i32);
+        match self.face {
+            BlockFace::North => [
+                origin.shift(uw, v, 0),
+                origin.shift(uw, 0, 0),
+                origin,
+                origin.shift(0, v, 0),
+            ],
+            BlockFace::South => [
+                origin.shift(0, v, 1),
+                origin.shift(0, 0, 1),
+                origin.shift(uw, 0, 1),
+                origin.shift(uw, v, 1),
+            ],
+            BlockFace::East => [
+                origin.shift(1, v, uw),
+                origin.shift(1, 0, uw),
+                origin.shift(1, 0, 0),
+                origin.shift(1, v, 0),
+            ],
+            BlockFace::West => [
+                origin.shift(0, v, 0),
+                origin,
+                origin.shift(0, 0, uw),
+                origin.shift(0, v, uw),
+            ],
+            BlockFace::Up => [
+                origin.shift(0, 1, 0),
+                origin.shift(0, 1, vw),
+                origin.shift(uw, 1, vw),
+                origin.shift(uw, 1, 0),
+            ],
+            BlockFace::Down => [
+                origin.shift(0, 0, vw),
+                origin,
+                origin.shift(uw, 0, 0),
+                origin.shift(uw, 0, vw),
+            ],
+        }
+    }
+
+    /// Corner positions as uploaded, with `Lowered` faces dropping their top edge
+    pub fn vertices(&self) -> [[f32; 3]; 4] {
+        // Lowered faces always cover a single block
+        let top = self.position.y + 1;
+        let lowered = self.shape == FaceShape::Lowered;
+        self.corners().map(|corner| {
+            let mut vertex: [f32; 3] = corner.into();
+            if lowered && corner.y == top {
+                vertex[1] -= LIQUID_DROP;
+            }
+            vertex
+        })
+    }
+
+    /// Texture info for each corner, repeating the (transformed) texture once per block
+    pub fn texture_info(&self) -> [TextureInfo; 4] {
+        let (u, v) = self.size;
+        self.texture
+            .into_arr_transformed(u as u8, v as u8, self.transform)
+    }
+
+    /// Packed tint (see `Tint::packed`) for each corner, from the climate of
+    /// the column the corner stands on
+    pub fn tints(&self, climate: &dyn ClimateSource) -> [u32; 4] {
+        if self.tint == Tint::None {
+            return [Tint::None.packed(Climate::default()); 4];
+        }
+        self.corners()
+            .map(|corner| self.tint.packed(climate.climate(corner.x, corner.z)))
+    }
+
+    /// Surface area in blocks
+    pub fn area(&self) -> usize {
+        self.size.0 as usize * self.size.1 as usize
+    }
+}
+
+/// GPU-independent triangle mesh, two triangles per face
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub tex_info: Vec<u32>,
+    /// Packed tint of each vertex, see `Face::tints`
+    pub tints: Vec<u32>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Add `face`, tinted for the default climate
+    pub fn push_face(&mut self, face: &Face) {
+        self.push_face_in(face, &Climate::default());
+    }
+
+    /// Add `face`, tinted for the climate `climate` gives its corners
+    pub fn push_face_in(&mut self, face: &Face, climate: &dyn ClimateSource) {
+        let base = self.positions.len() as u32;
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].iter().map(|x| x + base));
+        for (&position, &info) in face.vertices().iter().zip(&face.texture_info()) {
+            self.positions.push(position);
+            self.tex_info.push(info.into());
+        }
+        self.tints.extend(face.tints(climate));
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.indices.len() / 6
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+impl<'a> Extend<&'a Face> for Mesh {
+    fn extend<T: IntoIterator<Item = &'a Face>>(&mut self, iter: T) {
+        for face in iter {
+            self.push_face(face);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a Face> for Mesh {
+    fn from_iter<T: IntoIterator<Item = &'a Face>>(iter: T) -> Self {
+        let mut mesh = Self::default();
+        mesh.extend(iter);
+        mesh
+    }
+}
+
+/// Turns one chunk of any world layout into faces
+///
+/// Chunks missing from `world` (outside a bounded world, or not yet generated)
+/// produce no faces and count as empty neighbours.
+pub trait Mesher<
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    W: ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+>
+{
+    fn faces(
+        &self,
+        world: &W,
+        definitions: &EnumMap<Id, SolidBlockDefinition>,
+        coord: ChunkCoord,
+    ) -> Vec<Face>;
+
+    fn mesh(
+        &self,
+        world: &W,
+        definitions: &EnumMap<Id, SolidBlockDefinition>,
+        coord: ChunkCoord,
+    ) -> Mesh {
+        self.faces(world, definitions, coord).iter().collect()
+    }
+
+    /// `mesh` with faces tinted for the climate of `climate`, usually the
+    /// generator of `world`
+    fn mesh_in(
+        &self,
+        world: &W,
+        definitions: &EnumMap<Id, SolidBlockDefinition>,
+        coord: ChunkCoord,
+        climate: &dyn ClimateSource,
+    ) -> Mesh {
+        let mut mesh = Mesh::default();
+        for face in self.faces(world, definitions, coord) {
+            mesh.push_face_in(&face, climate);
+        }
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::once;
+
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        generator::{flat::Flat, WorldGenerator},
+        packs::{basic::*, Pack},
+        Block, BlockState, BlockSubPos, Chunk, ChunkPos, ChunkStorage, OctreeChunk, PaletteChunk,
+        StateKind, World,
+    };
+
+    use super::{binary::BinaryGreedy, culling::Culling, greedy::Greedy, *};
+
+    type TestWorld = World<BasicId, 4, 2, 4096, 16>;
+
+    type BlockAt = ((u16, u16), (u16, u16, u16));
+
+    fn world_with(blocks: &[BlockAt]) -> Box<TestWorld> {
+        let mut world = TestWorld::create();
+        for &((cx, cz), (x, y, z)) in blocks {
+            world[ChunkPos::new(cx, cz)][BlockSubPos::new(x, y, z)] = Block::solid(BasicId::Dirt);
+        }
+        world
+    }
+
+    fn meshers() -> Vec<Box<dyn Mesher<BasicId, 4096, 16, TestWorld>>> {
+        vec![Box::new(Culling), Box::new(Greedy), Box::new(BinaryGreedy)]
+    }
+
+    fn decode_uv(info: u32) -> (u32, u32) {
+        let repeat_u = (info >> 2) & 0x7F;
+        let repeat_v = (info >> 9) & 0x7F;
+        (((info & 2) >> 1) * repeat_u, (info & 1) * repeat_v)
+    }
+
+    #[test]
+    fn single_block_face_count() {
+        let world = world_with(&[((0, 0), (3, 4, 5))]);
+        for mesher in meshers() {
+            let mesh = mesher.mesh(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0));
+            assert_eq!(mesh.face_count(), 6);
+            assert_eq!(mesh.positions.len(), 24);
+            assert_eq!(mesh.tex_info.len(), 24);
+            assert_eq!(mesh.indices.len(), 36);
+        }
+    }
+
+    #[test]
+    fn culls_across_chunk_edges() {
+        let world = world_with(&[((0, 0), (15, 0, 0)), ((1, 0), (0, 0, 0))]);
+        for mesher in meshers() {
+            for &(cx, cz) in &[(0, 0), (1, 0)] {
+                let mesh = mesher.mesh(
+                    world.as_ref(),
+                    BasicPack::get_map(),
+                    ChunkCoord::new(cx, cz),
+                );
+                assert_eq!(mesh.face_count(), 5);
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_merges_row() {
+        let world = world_with(&[
+            ((0, 0), (0, 0, 0)),
+            ((0, 0), (1, 0, 0)),
+            ((0, 0), (2, 0, 0)),
+        ]);
+        let pos = ChunkCoord::new(0, 0);
+        assert_eq!(
+            Culling
+                .mesh(world.as_ref(), BasicPack::get_map(), pos)
+                .face_count(),
+            14
+        );
+        for mesher in &[
+            &Greedy as &dyn Mesher<_, 4096, 16, TestWorld>,
+            &BinaryGreedy,
+        ] {
+            let faces = mesher.faces(world.as_ref(), BasicPack::get_map(), pos);
+            assert_eq!(faces.len(), 6);
+            assert_eq!(faces.iter().map(Face::area).sum::<usize>(), 14);
+            let mesh = mesher.mesh(world.as_ref(), BasicPack::get_map(), pos);
+            for (face, infos) in faces.iter().zip(mesh.tex_info.chunks(4)) {
+                let (u, v) = face.size;
+                let max = infos.iter().map(|&info| decode_uv(info)).max().unwrap();
+                assert_eq!(max, (u as u32, v as u32));
+            }
+        }
+    }
+
+    fn assert_winds_outwards(face: &Face) {
+        let mesh: Mesh = once(face).collect();
+        let (dx, dy, dz) = face.face.offset();
+        for triangle in mesh.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[triangle[i] as usize]);
+            let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let normal = [
+                ab[1] * ac[2] - ab[2] * ac[1],
+                ab[2] * ac[0] - ab[0] * ac[2],
+                ab[0] * ac[1] - ab[1] * ac[0],
+            ];
+            let dot = normal[0] * dx as f32 + normal[1] * dy as f32 + normal[2] * dz as f32;
+            assert!(dot > 0.0, "{:?} winds inwards", face.face);
+        }
+    }
+
+    #[test]
+    fn winding_faces_outwards() {
+        let world = world_with(&[((0, 0), (3, 4, 5))]);
+        for face in Culling.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0)) {
+            assert_winds_outwards(&face);
+        }
+    }
+
+    #[test]
+    fn single_face_uvs() {
+        let world = world_with(&[((0, 0), (3, 4, 5))]);
+        let definition = &BasicPack::get_map()[BasicId::Dirt];
+        for face in Culling.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0)) {
+            let mesh: Mesh = once(&face).collect();
+            let uvs: Vec<_> = mesh.tex_info.iter().map(|&info| decode_uv(info)).collect();
+            assert_eq!(uvs, vec![(0, 0), (0, 1), (1, 1), (1, 0)]);
+            for &info in &mesh.tex_info {
+                assert_eq!(info >> 16, definition[face.face].0 as u32);
+            }
+        }
+    }
+
+    #[test]
+    fn state_selects_texture() {
+        let mut world = TestWorld::create();
+        let trunk = Block::with_state(BasicId::Trunk, TrunkAxis::X);
+        world.set(WorldPosition::new(3, 4, 5), trunk).unwrap();
+        let definition = &BasicPack::get_map()[BasicId::Trunk];
+        assert_eq!(definition.state_count(), 3);
+        let ring = definition[BlockFace::Up];
+        for face in Culling.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0)) {
+            let ends = face.face == BlockFace::East || face.face == BlockFace::West;
+            assert_eq!(face.texture == ring, ends, "{:?}", face.face);
+        }
+        assert_eq!(
+            TrunkAxis::from_state(TrunkAxis::Z.into_state()),
+            Some(TrunkAxis::Z)
+        );
+    }
+
+    fn world_of(blocks: &[(BasicId, (i32, u16, i32))]) -> Box<TestWorld> {
+        let mut world = TestWorld::create();
+        for &(id, (x, y, z)) in blocks {
+            world
+                .set(WorldPosition::new(x, y, z), Block::solid(id))
+                .unwrap();
+        }
+        world
+    }
+
+    #[test]
+    fn transparent_hides_own_kind_only() {
+        let world = world_of(&[
+            (BasicId::Glass, (3, 4, 5)),
+            (BasicId::Glass, (4, 4, 5)),
+            (BasicId::Dirt, (5, 4, 5)),
+        ]);
+        for mesher in meshers() {
+            let faces = mesher.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0));
+            let area = |id: BasicId| {
+                let texture = BasicPack::get_map()[id][BlockFace::North];
+                faces
+                    .iter()
+                    .filter(|face| face.texture == texture)
+                    .map(Face::area)
+                    .sum::<usize>()
+            };
+            // The glass pair loses the faces between the panes and against
+            // the dirt, the dirt keeps the face behind the glass
+            assert_eq!(area(BasicId::Glass), 9);
+            assert_eq!(area(BasicId::Dirt), 6);
+        }
+    }
+
+    #[test]
+    fn merged_faces_keep_variant_transforms() {
+        let blocks: Vec<_> = (0..8)
+            .flat_map(|x| (0..8).map(move |z| (BasicId::DirtGrass, (x, 4, z))))
+            .collect();
+        let world = world_of(&blocks);
+        let definition = &BasicPack::get_map()[BasicId::DirtGrass];
+        for mesher in meshers() {
+            let faces = mesher.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0));
+            let mut area = 0;
+            for face in faces.iter().filter(|face| face.face == BlockFace::Up) {
+                // Every block under a quad shows the texture the quad does
+                for dx in 0..face.size.0 as i32 {
+                    for dz in 0..face.size.1 as i32 {
+                        let position = face.position.shift(dx, 0, dz);
+                        let at =
+                            definition.texture_at(BlockState::default(), BlockFace::Up, position);
+                        assert_eq!((face.texture, face.transform), at);
+                    }
+                }
+                area += face.area();
+            }
+            assert_eq!(area, 64);
+        }
+    }
+
+    #[test]
+    fn plane_sides_follow_texture_axes() {
+        let step = |from: WorldPosition, to: WorldPosition| {
+            (to.x - from.x, to.y as i32 - from.y as i32, to.z - from.z)
+        };
+        for face in BlockFace::iter() {
+            let quad = Face {
+                position: WorldPosition::new(4, 4, 4),
+                face,
+                texture: TextureIndex(0),
+                transform: TextureTransform::IDENTITY,
+                tint: Tint::None,
+                size: (1, 1),
+                shape: FaceShape::Cube,
+            };
+            let [top_left, bottom_left, _, top_right] = quad.corners();
+            let sides = face.plane_sides();
+            assert_eq!(step(top_left, top_right), sides[1].offset());
+            assert_eq!(step(top_left, bottom_left), sides[3].offset());
+            let opposite = |(x, y, z): (i32, i32, i32)| (-x, -y, -z);
+            assert_eq!(sides[0].offset(), opposite(sides[1].offset()));
+            assert_eq!(sides[2].offset(), opposite(sides[3].offset()));
+        }
+    }
+
+    #[test]
+    fn connected_panes_join() {
+        // A 3x3 pane in the x-y plane
+        let blocks: Vec<_> = (0..3)
+            .flat_map(|x| (0..3).map(move |y| (BasicId::GlassFrame, (4 + x, 4 + y, 5))))
+            .collect();
+        let world = world_of(&blocks);
+        let definition = &BasicPack::get_map()[BasicId::GlassFrame];
+        let tiles = definition.connected(definition[BlockFace::North]).unwrap();
+        for mesher in meshers() {
+            let faces = mesher.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0));
+            let tile_at = |x, y| {
+                faces
+                    .iter()
+                    .find(|face| {
+                        face.face == BlockFace::South
+                            && face.position.x == x
+                            && face.position.y == y
+                    })
+                    .map(|face| face.texture)
+            };
+            // Left, right, top and bottom of a South face are West, East, Up, Down
+            assert_eq!(tile_at(5, 5), Some(tiles[0b1111]));
+            assert_eq!(tile_at(4, 4), Some(tiles[0b0110]));
+            assert_eq!(tile_at(6, 6), Some(tiles[0b1001]));
+            // The top row faces up, joined along x only
+            let top: Vec<_> = faces
+                .iter()
+                .filter(|face| face.face == BlockFace::Up)
+                .map(|face| (face.position.x, face.texture))
+                .collect();
+            assert!(top.contains(&(4, tiles[0b0010])));
+        }
+    }
+
+    #[test]
+    fn grass_tops_are_tinted() {
+        let world = world_of(&[(BasicId::DirtGrass, (3, 4, 5)), (BasicId::Dirt, (4, 4, 5))]);
+        let hot = Climate {
+            temperature: 1.0,
+            humidity: 0.0,
+        };
+        for mesher in meshers() {
+            let mesh = mesher.mesh_in(
+                world.as_ref(),
+                BasicPack::get_map(),
+                ChunkCoord::new(0, 0),
+                &hot,
+            );
+            assert_eq!(mesh.tints.len(), mesh.positions.len());
+            let tinted: Vec<_> = mesh
+                .tints
+                .iter()
+                .zip(&mesh.positions)
+                .filter(|(&tint, _)| tint != Tint::None.packed(hot))
+                .collect();
+            // Only the four corners of the grass top
+            assert_eq!(tinted.len(), 4);
+            for (&tint, position) in tinted {
+                assert_eq!(tint, Tint::Grass.packed(hot));
+                assert_eq!(position[1], 5.0);
+            }
+        }
+    }
+
+    #[test]
+    fn cross_plants_emit_diagonals() {
+        let world = world_of(&[(BasicId::Dirt, (3, 4, 5)), (BasicId::Grass, (3, 5, 5))]);
+        for mesher in meshers() {
+            let faces = mesher.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0));
+            let cross: Vec<_> = faces
+                .iter()
+                .filter(|face| face.shape == FaceShape::Cross)
+                .collect();
+            assert_eq!(cross.len(), 4);
+            // A plant covers nothing, the dirt below keeps its top
+            assert_eq!(faces.len(), 10);
+            for face in cross {
+                assert_winds_outwards(face);
+            }
+        }
+    }
+
+    #[test]
+    fn liquid_surface_is_lowered() {
+        let world = world_of(&[(BasicId::Water, (3, 4, 5)), (BasicId::Water, (3, 5, 5))]);
+        for mesher in meshers() {
+            let faces = mesher.faces(world.as_ref(), BasicPack::get_map(), ChunkCoord::new(0, 0));
+            // Both blocks lose the face between them
+            assert_eq!(faces.len(), 10);
+            for face in &faces {
+                let surface = face.position.y == 5;
+                assert_eq!(face.shape == FaceShape::Lowered, surface);
+                if face.face == BlockFace::Down {
+                    continue;
+                }
+                let top = face
+                    .vertices()
+                    .iter()
+                    .map(|vertex| vertex[1])
+                    .fold(f32::MIN, f32::max);
+                let expected = face.position.y as f32 + 1.0;
+                if surface {
+                    assert_eq!(top, expected - LIQUID_DROP);
+                } else {
+                    assert_eq!(top, expected);
+                }
+            }
+        }
+    }
+
+    fn flat_face_count<S: ChunkStorage<BasicId, 4096, 16>>() -> usize {
+        let mut world = World::<BasicId, 4, 2, 4096, 16, S>::create();
+        Flat::new_simple(5).generate(world.as_mut());
+        world
+            .as_ref()
+            .into_iter()
+            .map(|(pos, _)| {
+                Culling
+                    .mesh(world.as_ref(), BasicPack::get_map(), pos.into())
+                    .face_count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn same_mesh_on_every_storage() {
+        let dense = flat_face_count::<Chunk<BasicId, 4096, 16>>();
+        assert_eq!(flat_face_count::<PaletteChunk<BasicId, 4096, 16>>(), dense);
+        assert_eq!(flat_face_count::<OctreeChunk<BasicId, 4096, 16>>(), dense);
+    }
+}
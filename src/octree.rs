@@ -0,0 +1,162 @@
+use std::{fmt::Display, ops::Index};
+
+use crate::{Block, BlockId, BlockSubPos, ChunkStorage};
+
+#[derive(Debug, Clone)]
+enum Node<Id: BlockId> {
+    Leaf(Block<Id>),
+    Branch(Box<[Node<Id>; 8]>),
+}
+
+impl<Id: BlockId> Node<Id> {
+    fn child_index(half: usize, x: usize, y: usize, z: usize) -> usize {
+        (x & half != 0) as usize | ((y & half != 0) as usize) << 1 | ((z & half != 0) as usize) << 2
+    }
+
+    fn set(&mut self, size: usize, (x, y, z): (usize, usize, usize), block: Block<Id>) {
+        if size == 1 {
+            *self = Node::Leaf(block);
+            return;
+        }
+        if let Node::Leaf(current) = *self {
+            if current == block {
+                return;
+            }
+            *self = Node::Branch(Box::new([(); 8].map(|_| Node::Leaf(current))));
+        }
+        if let Node::Branch(children) = self {
+            let half = size / 2;
+            children[Self::child_index(half, x, y, z)].set(half, (x, y, z), block);
+            if children
+                .iter()
+                .all(|child| matches!(child, Node::Leaf(leaf) if *leaf == block))
+            {
+                *self = Node::Leaf(block);
+            }
+        }
+    }
+
+    fn branches(&self) -> usize {
+        match self {
+            Node::Leaf(_) => 0,
+            Node::Branch(children) => 1 + children.iter().map(Node::branches).sum::<usize>(),
+        }
+    }
+}
+
+/// Chunk stored as a sparse octree, collapsing uniform regions into single leaves
+///
+/// The tree covers the smallest power-of-two cube enclosing the chunk; the part
+/// outside the chunk is never written and stays empty.
+#[derive(Debug, Clone)]
+pub struct OctreeChunk<Id: BlockId, const SIZE: usize, const WIDTH: usize> {
+    root: Node<Id>,
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> Default for OctreeChunk<Id, SIZE, WIDTH> {
+    fn default() -> Self {
+        Self {
+            root: Node::Leaf(Block::Empty),
+        }
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> Display for OctreeChunk<Id, SIZE, WIDTH> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "OctreeChunk<{}, {}, {}>",
+            std::any::type_name::<Id>(),
+            SIZE,
+            WIDTH
+        )?;
+        for (pos, block) in self.iter_blocks() {
+            writeln!(f, "{}: {}", pos, block)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> OctreeChunk<Id, SIZE, WIDTH> {
+    pub const HEIGHT: usize = SIZE / WIDTH / WIDTH;
+    /// Side length of the cube covered by the root node
+    pub const SIDE: usize = if WIDTH > Self::HEIGHT {
+        WIDTH.next_power_of_two()
+    } else {
+        Self::HEIGHT.next_power_of_two()
+    };
+
+    fn coords(pos: BlockSubPos<SIZE, WIDTH>) -> (usize, usize, usize) {
+        let (x, y, z) = pos.into();
+        (x as usize, y as usize, z as usize)
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> Index<BlockSubPos<SIZE, WIDTH>>
+    for OctreeChunk<Id, SIZE, WIDTH>
+{
+    type Output = Block<Id>;
+
+    fn index(&self, index: BlockSubPos<SIZE, WIDTH>) -> &Self::Output {
+        let (x, y, z) = Self::coords(index);
+        let mut node = &self.root;
+        let mut half = Self::SIDE / 2;
+        loop {
+            match node {
+                Node::Leaf(block) => return block,
+                Node::Branch(children) => {
+                    node = &children[Node::<Id>::child_index(half, x, y, z)];
+                    half /= 2;
+                }
+            }
+        }
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> ChunkStorage<Id, SIZE, WIDTH>
+    for OctreeChunk<Id, SIZE, WIDTH>
+{
+    fn set(&mut self, pos: BlockSubPos<SIZE, WIDTH>, block: Block<Id>) {
+        self.root.set(Self::SIDE, Self::coords(pos), block);
+    }
+
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>() + self.root.branches() * std::mem::size_of::<[Node<Id>; 8]>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{packs::basic::BasicId, BlockSubPosIterator};
+
+    use super::*;
+
+    type TestChunk = OctreeChunk<BasicId, 4096, 16>;
+
+    #[test]
+    fn collapses_uniform_regions() {
+        let mut chunk = TestChunk::default();
+        chunk.fill(|_| Block::solid(BasicId::Stone));
+        for pos in BlockSubPosIterator::<4096, 16>::default() {
+            chunk.set(pos, Block::Empty);
+        }
+        assert_eq!(chunk.root.branches(), 0);
+    }
+
+    #[test]
+    fn stores_every_block() {
+        let mut chunk = TestChunk::default();
+        let block_at = |pos: BlockSubPos<4096, 16>| {
+            let (x, y, z) = pos.into();
+            if (x ^ y ^ z) % 3 == 0 {
+                Block::solid(BasicId::Sand)
+            } else {
+                Block::Empty
+            }
+        };
+        chunk.fill(block_at);
+        for (pos, block) in chunk.iter_blocks() {
+            assert_eq!(block, block_at(pos));
+        }
+    }
+}
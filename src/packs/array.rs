@@ -0,0 +1,237 @@
+use std::fmt::Display;
+
+use glium::{
+    backend::Facade,
+    texture::{
+        pixel_buffer::PixelBuffer, srgb_texture2d_array::SrgbTexture2dArray, MipmapsOption,
+        RawImage2d, TextureCreationError,
+    },
+};
+use image::{imageops::FilterType, DynamicImage, GenericImage, GenericImageView, Rgba, RgbaImage};
+
+/// How layers of another size are brought to the array resolution
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpriteFit {
+    /// Scale to the array size, with nearest filtering when enlarging so pixel
+    /// art stays sharp
+    #[default]
+    Resample,
+    /// Keep smaller layers as they are in the top-left corner, surrounded by
+    /// transparency; larger layers are still scaled down
+    Pad,
+}
+
+/// Shape of the layers handed to the GPU
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ArrayOptions {
+    /// Side of every layer, the largest layer side when `None`
+    pub size: Option<u32>,
+    pub fit: SpriteFit,
+    /// Compute the mip levels on the CPU instead of letting the driver do it
+    pub mipmaps: bool,
+}
+
+/// First layer whose size differs from the layer before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MixedSizes {
+    pub index: usize,
+    pub expected: (u32, u32),
+    pub found: (u32, u32),
+}
+
+impl Display for MixedSizes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "layer {} is {}x{}, expected {}x{}",
+            self.index, self.found.0, self.found.1, self.expected.0, self.expected.1
+        )
+    }
+}
+
+impl std::error::Error for MixedSizes {}
+
+/// Size shared by every layer, the way `SrgbTexture2dArray::new` needs them
+pub fn check_uniform(layers: &[RawImage2d<u8>]) -> Result<Option<(u32, u32)>, MixedSizes> {
+    let mut sizes = layers.iter().map(|layer| (layer.width, layer.height));
+    let expected = match sizes.next() {
+        Some(size) => size,
+        None => return Ok(None),
+    };
+    match sizes.position(|size| size != expected) {
+        Some(index) => Err(MixedSizes {
+            index: index + 1,
+            found: (layers[index + 1].width, layers[index + 1].height),
+            expected,
+        }),
+        None => Ok(Some(expected)),
+    }
+}
+
+/// Square RGBA layers of one size, with their mip chain when computed on the CPU
+pub struct TextureArray {
+    size: u32,
+    /// `levels[0]` holds the full size layers, each next level halves them
+    levels: Vec<Vec<RgbaImage>>,
+}
+
+impl TextureArray {
+    /// Bring RGBA `layers` (as `Pack::get_textures` returns them) to one size
+    pub fn new(layers: Vec<RawImage2d<u8>>, options: &ArrayOptions) -> Self {
+        let size = options.size.unwrap_or_else(|| {
+            layers
+                .iter()
+                .map(|layer| layer.width.max(layer.height))
+                .max()
+                .unwrap_or(1)
+        });
+        let base: Vec<_> = layers
+            .into_iter()
+            .map(|layer| {
+                let image = RgbaImage::from_raw(layer.width, layer.height, layer.data.into_owned())
+                    .expect("layer data does not match its size");
+                fit(DynamicImage::ImageRgba8(image), size, options.fit)
+            })
+            .collect();
+        let mut levels = vec![base];
+        if options.mipmaps {
+            let mut side = size;
+            while side > 1 {
+                side /= 2;
+                let level = levels[levels.len() - 1]
+                    .iter()
+                    .map(|layer| image::imageops::resize(layer, side, side, FilterType::Triangle))
+                    .collect();
+                levels.push(level);
+            }
+        }
+        Self { size, levels }
+    }
+
+    /// Side of the full size layers
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// Number of mip levels, the full size included
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Layers of mip `level`
+    pub fn level(&self, level: usize) -> &[RgbaImage] {
+        &self.levels[level]
+    }
+
+    /// Create the GPU texture, with the CPU mip levels if there are any and
+    /// driver generated ones otherwise
+    pub fn upload<F: Facade>(
+        &self,
+        facade: &F,
+    ) -> Result<SrgbTexture2dArray, TextureCreationError> {
+        if self.levels.len() == 1 {
+            let layers = self.levels[0]
+                .iter()
+                .map(|layer| RawImage2d::from_raw_rgba(layer.as_raw().clone(), layer.dimensions()))
+                .collect();
+            return SrgbTexture2dArray::new(facade, layers);
+        }
+        let texture = SrgbTexture2dArray::empty_with_mipmaps(
+            facade,
+            MipmapsOption::EmptyMipmapsMax(self.levels.len() as u32 - 1),
+            self.size,
+            self.size,
+            self.layer_count() as u32,
+        )?;
+        // glium only writes whole array levels through a pixel buffer
+        for (level, layers) in self.levels.iter().enumerate() {
+            let side = self.size >> level;
+            let pixels: Vec<(u8, u8, u8, u8)> = layers
+                .iter()
+                .flat_map(|layer| layer.pixels().map(|&Rgba([r, g, b, a])| (r, g, b, a)))
+                .collect();
+            let buffer = PixelBuffer::new_empty(facade, pixels.len());
+            buffer.write(&pixels);
+            texture
+                .mipmap(level as u32)
+                .expect("mip level was allocated")
+                .raw_upload_from_pixel_buffer(
+                    buffer.as_slice(),
+                    0..side,
+                    0..side,
+                    0..layers.len() as u32,
+                );
+        }
+        Ok(texture)
+    }
+}
+
+fn fit(image: DynamicImage, size: u32, fit: SpriteFit) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    if (width, height) == (size, size) {
+        return image.into_rgba8();
+    }
+    let enlarging = width < size && height < size;
+    match fit {
+        SpriteFit::Pad if enlarging => {
+            let mut padded = DynamicImage::new_rgba8(size, size);
+            padded.copy_from(&image, 0, 0).unwrap();
+            padded.into_rgba8()
+        }
+        _ => {
+            let filter = if enlarging {
+                FilterType::Nearest
+            } else {
+                FilterType::Triangle
+            };
+            image.resize_exact(size, size, filter).into_rgba8()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(size: u32) -> RawImage2d<'static, u8> {
+        let image = RgbaImage::from_pixel(size, size, Rgba([200, 100, 50, 255]));
+        RawImage2d::from_raw_rgba(image.into_raw(), (size, size))
+    }
+
+    #[test]
+    fn evens_out_mixed_sizes() {
+        assert_eq!(check_uniform(&[layer(16), layer(16)]), Ok(Some((16, 16))));
+        assert_eq!(
+            check_uniform(&[layer(16), layer(16), layer(8)]),
+            Err(MixedSizes {
+                index: 2,
+                expected: (16, 16),
+                found: (8, 8)
+            })
+        );
+        let options = ArrayOptions::default();
+        let array = TextureArray::new(vec![layer(16), layer(8), layer(32)], &options);
+        assert_eq!(array.size(), 32);
+        assert_eq!(array.level_count(), 1);
+        for layer in array.level(0) {
+            assert_eq!(layer.dimensions(), (32, 32));
+            assert_eq!(*layer.get_pixel(31, 31), Rgba([200, 100, 50, 255]));
+        }
+        let options = ArrayOptions {
+            size: Some(16),
+            fit: SpriteFit::Pad,
+            mipmaps: true,
+        };
+        let array = TextureArray::new(vec![layer(8), layer(32)], &options);
+        assert_eq!(array.level_count(), 5);
+        assert_eq!(array.level(4)[0].dimensions(), (1, 1));
+        let padded = &array.level(0)[0];
+        assert_eq!(padded.get_pixel(7, 7)[3], 255);
+        assert_eq!(padded.get_pixel(8, 8)[3], 0);
+        assert_eq!(array.level(0)[1].dimensions(), (16, 16));
+    }
+}
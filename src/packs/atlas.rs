@@ -0,0 +1,243 @@
+use std::{fmt::Write, path::Path};
+
+use glium::texture::RawImage2d;
+use image::{DynamicImage, GenericImage, GenericImageView};
+
+use super::{
+    loader::PackError,
+    utils::{SpriteArray, SpriteDefinition},
+};
+
+/// Collects individual tile images and packs them into an atlas
+///
+/// Tiles keep the order they were added in, which becomes their texture index
+/// both in the packed atlas and in `texture_array`.
+#[derive(Default)]
+pub struct AtlasBuilder {
+    tiles: Vec<(String, DynamicImage)>,
+}
+
+impl AtlasBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every `.png` file directly inside `dir`, sorted by file name and named
+    /// after it (`dirt.png`), as TexturePacker does
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, PackError> {
+        let dir = dir.as_ref();
+        let io_error = |err| PackError::Io(dir.into(), err);
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.extension().is_some_and(|ext| ext == "png") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        let mut builder = Self::new();
+        for path in paths {
+            let image = image::open(&path).map_err(|err| PackError::Image(path.clone(), err))?;
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            builder.add(name, image);
+        }
+        Ok(builder)
+    }
+
+    pub fn add(&mut self, name: impl Into<String>, image: DynamicImage) -> &mut Self {
+        self.tiles.push((name.into(), image));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// The tiles as they are, one layer per tile, skipping the atlas entirely
+    pub fn texture_array(&self) -> Vec<RawImage2d<'static, u8>> {
+        self.tiles
+            .iter()
+            .map(|(_, image)| {
+                let dim = image.dimensions();
+                RawImage2d::from_raw_rgba(image.to_rgba8().into_raw(), dim)
+            })
+            .collect()
+    }
+
+    /// Pack the tiles in rows (shelves), tallest first, into a roughly square
+    /// image whose width is a multiple of the widest tile
+    pub fn build(&self) -> Atlas {
+        let area: u32 = self
+            .tiles
+            .iter()
+            .map(|(_, image)| image.width() * image.height())
+            .sum();
+        let widest = self
+            .tiles
+            .iter()
+            .map(|(_, image)| image.width())
+            .max()
+            .unwrap_or(1);
+        let side = (area as f64).sqrt().ceil() as u32;
+        let width = side.div_ceil(widest).max(1) * widest;
+        let mut order: Vec<_> = (0..self.tiles.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(self.tiles[index].1.height()));
+        let mut sprites = vec![SpriteDefinition::default(); self.tiles.len()];
+        let (mut x, mut y, mut shelf) = (0, 0, 0);
+        for index in order {
+            let image = &self.tiles[index].1;
+            if x + image.width() > width {
+                x = 0;
+                y += shelf;
+                shelf = 0;
+            }
+            sprites[index] = SpriteDefinition::from((x, y, image.width(), image.height()));
+            x += image.width();
+            shelf = shelf.max(image.height());
+        }
+        let mut image = DynamicImage::new_rgba8(width, y + shelf);
+        for ((_, tile), sprite) in self.tiles.iter().zip(&sprites) {
+            // Every sprite lies inside the image by construction
+            image.copy_from(tile, sprite.x, sprite.y).unwrap();
+        }
+        Atlas {
+            image,
+            sprites: self
+                .tiles
+                .iter()
+                .map(|(name, _)| name.clone())
+                .zip(sprites)
+                .collect(),
+        }
+    }
+}
+
+/// Packed atlas image along with where each tile ended up
+pub struct Atlas {
+    pub image: DynamicImage,
+    /// Tile names and areas, in texture index order
+    pub sprites: Vec<(String, SpriteDefinition)>,
+}
+
+impl Atlas {
+    /// Same lookup `spritesheet!` builds for a compiled-in atlas
+    pub fn sprite_array(&self) -> SpriteArray {
+        self.sprites.iter().cloned().collect()
+    }
+
+    /// One image per sprite, cut back out of the atlas
+    pub fn textures(&self) -> Vec<RawImage2d<'static, u8>> {
+        self.sprite_array().get_image_array(&self.image)
+    }
+
+    /// TexturePacker XML describing the atlas, referring to `image_path`
+    pub fn to_xml(&self, image_path: &str) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        writeln!(xml, "<TextureAtlas imagePath=\"{}\">", escape(image_path)).unwrap();
+        for (name, sprite) in &self.sprites {
+            writeln!(
+                xml,
+                "    <SubTexture name=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                escape(name),
+                sprite.x,
+                sprite.y,
+                sprite.width,
+                sprite.height
+            )
+            .unwrap();
+        }
+        xml.push_str("</TextureAtlas>\n");
+        xml
+    }
+
+    /// Write the atlas image to `image_path` and its XML to `xml_path`, the XML
+    /// refers to the image by file name so both belong in the same directory
+    pub fn save(
+        &self,
+        xml_path: impl AsRef<Path>,
+        image_path: impl AsRef<Path>,
+    ) -> Result<(), PackError> {
+        let (xml_path, image_path) = (xml_path.as_ref(), image_path.as_ref());
+        self.image
+            .save(image_path)
+            .map_err(|err| PackError::Image(image_path.into(), err))?;
+        let name = image_path.file_name().unwrap_or_default().to_string_lossy();
+        std::fs::write(xml_path, self.to_xml(&name))
+            .map_err(|err| PackError::Io(xml_path.into(), err))
+    }
+}
+
+/// `value` made safe inside a double quoted XML attribute
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use crate::packs::loader::parse_atlas;
+
+    use super::*;
+
+    fn tile(size: u32, shade: u8) -> DynamicImage {
+        DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            size,
+            size,
+            Rgba([shade, 255 - shade, shade / 2, 255]),
+        ))
+    }
+
+    #[test]
+    fn packs_tiles_without_overlap() {
+        let mut builder = AtlasBuilder::new();
+        for (index, size) in [16, 32, 16, 8, 16].iter().enumerate() {
+            builder.add(format!("tile{}.png", index), tile(*size, index as u8 * 40));
+        }
+        let atlas = builder.build();
+        let (width, height) = atlas.image.dimensions();
+        for (index, (_, a)) in atlas.sprites.iter().enumerate() {
+            assert!(a.x + a.width <= width && a.y + a.height <= height);
+            for (_, b) in &atlas.sprites[index + 1..] {
+                let apart = a.x + a.width <= b.x
+                    || b.x + b.width <= a.x
+                    || a.y + a.height <= b.y
+                    || b.y + b.height <= a.y;
+                assert!(apart);
+            }
+        }
+        let packed = atlas.textures();
+        for (packed, direct) in packed.iter().zip(&builder.texture_array()) {
+            assert_eq!(packed.data, direct.data);
+        }
+        let (image_path, sprites) = parse_atlas(&atlas.to_xml("atlas.png")).unwrap();
+        assert_eq!(image_path, "atlas.png");
+        assert_eq!(sprites.sprites().len(), 5);
+        for (index, (name, sprite)) in atlas.sprites.iter().enumerate() {
+            assert_eq!(sprites.get(name).unwrap().0 as usize, index);
+            assert!(sprites.sprites()[index] == *sprite);
+        }
+    }
+
+    #[test]
+    fn xml_round_trips_special_names() {
+        let mut builder = AtlasBuilder::new();
+        for name in ["say \"hi\".png", "a&b.png", "<x>.png", "&amp;.png"] {
+            builder.add(name, tile(4, 0));
+        }
+        let atlas = builder.build();
+        let (image_path, sprites) = parse_atlas(&atlas.to_xml("a&\"b\".png")).unwrap();
+        assert_eq!(image_path, "a&\"b\".png");
+        for (index, (name, _)) in atlas.sprites.iter().enumerate() {
+            assert_eq!(sprites.get(name).unwrap().0 as usize, index);
+        }
+    }
+}
@@ -1,13 +1,15 @@
 use super::{
-    utils::{SpriteArray, SpriteDefinition},
+    loader::parse_atlas,
+    utils::{connected_tiles, SpriteArray},
+    validation::{validate, Report},
     Pack, SimpleBlockId,
 };
 use crate::*;
 use enum_map::{enum_map, Enum};
-use image::DynamicImage;
+use image::{DynamicImage, GenericImageView, RgbaImage};
 use lazy_static::lazy_static;
-use rand::Rng;
-use strum::{EnumCount, IntoEnumIterator};
+use std::time::Duration;
+use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumCount, EnumIter};
 
 pub struct BasicPack;
@@ -28,8 +30,82 @@ pub enum BasicId {
     RedSand,
     GreyStone,
     GreySand,
+    /// Log, oriented by `TrunkAxis`
+    Trunk,
+    /// Crop, growing through `WheatStage`
+    Wheat,
+    /// Ores come in two looks, see `OreVariant`
+    StoneCoal,
+    StoneIron,
+    StoneGold,
+    StoneDiamond,
+    GreyStoneRuby,
+    RedStoneEmerald,
+    Glass,
+    Leaves,
+    /// Tuft of grass, in one of the `GrassStyle` looks
+    Grass,
+    /// Mushroom, coloured by `MushroomColor`
+    Mushroom,
+    Water,
+    Lava,
+    /// Framed glass, joining into continuous panes
+    GlassFrame,
 }
 
+/// Axis a `BasicId::Trunk` runs along, its rings face both ends
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter)]
+pub enum TrunkAxis {
+    Y,
+    X,
+    Z,
+}
+
+impl StateKind for TrunkAxis {}
+
+/// Growth stage of `BasicId::Wheat`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter)]
+pub enum WheatStage {
+    Stage1,
+    Stage2,
+    Stage3,
+    Stage4,
+}
+
+impl StateKind for WheatStage {}
+
+/// Texture variant of the ore blocks
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter)]
+pub enum OreVariant {
+    /// Mostly the plain look, with the alt look mixed in by position
+    Plain,
+    /// Always the alt look
+    Alt,
+}
+
+impl StateKind for OreVariant {}
+
+/// Look of a `BasicId::Grass` tuft
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter)]
+pub enum GrassStyle {
+    Short,
+    Medium,
+    Tall,
+    Flowering,
+}
+
+impl StateKind for GrassStyle {}
+
+/// Colour of a `BasicId::Mushroom`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter)]
+pub enum MushroomColor {
+    Brown,
+    Red,
+    Tan,
+}
+
+impl StateKind for MushroomColor {}
+
 impl SimpleBlockId for BasicId {
     fn get_simple_block() -> Self {
         Self::Dirt
@@ -39,124 +115,92 @@ impl SimpleBlockId for BasicId {
         Self::DirtGrass
     }
 
-    fn get_random_block() -> Self {
-        let tmp = rand::thread_rng().gen_range(0..BasicId::COUNT);
-        BasicId::iter().nth(tmp as usize).unwrap()
+    fn random_blocks() -> &'static [Self] {
+        &RANDOM
     }
 }
 
+/// Sprites animated by scrolling them, their extra frames are appended to the
+/// atlas layers in this order
+const SCROLLING: [&str; 2] = ["water.png", "lava.png"];
+
+/// Frames of a scrolling sprite, the first being the sprite itself
+const SCROLL_FRAMES: usize = 4;
+
+/// Animation of `SCROLLING[index]`, see `BasicPack::get_textures`
+fn scrolling(index: usize) -> Animation {
+    let first = SPRITESHEET.sprites().len() + index * (SCROLL_FRAMES - 1);
+    let frames = std::iter::once(SPRITESHEET[SCROLLING[index]])
+        .chain((first..first + SCROLL_FRAMES - 1).map(|layer| TextureIndex(layer as u16)))
+        .collect();
+    Animation::new(frames, Duration::from_millis(250))
+}
+
+/// Framed sprites with connected tiles, appended to the atlas layers after the
+/// `SCROLLING` frames in this order
+const CONNECTED: [&str; 1] = ["glass_frame.png"];
+
+/// Width of the frame of a `CONNECTED` sprite of width `width`, corners included
+fn frame_border(width: u32) -> u32 {
+    width * 5 / 32
+}
+
+/// Connected tiles of `CONNECTED[index]`, the first being the sprite itself
+fn connected(index: usize) -> [TextureIndex; 16] {
+    let first = SPRITESHEET.sprites().len() + SCROLLING.len() * (SCROLL_FRAMES - 1) + index * 15;
+    let mut tiles = [SPRITESHEET[CONNECTED[index]]; 16];
+    for (mask, tile) in tiles.iter_mut().enumerate().skip(1) {
+        *tile = TextureIndex((first + mask - 1) as u16);
+    }
+    tiles
+}
+
+/// Definition switching between `$name` and `${name}_alt` by `OreVariant`
+macro_rules! ore {
+    ($name:literal) => {{
+        let alt = SPRITESHEET[concat!($name, "_alt.png")];
+        SolidBlockDefinition::with_states(|variant| match variant {
+            OreVariant::Plain => sprite!($name),
+            OreVariant::Alt => alt.into(),
+        })
+        .with_variants(
+            sprite!($name),
+            vec![Variant::new(sprite!($name), 3), Variant::new(alt, 1)],
+        )
+    }};
+}
+
 lazy_static! {
     static ref TILES: DynamicImage = image::load_from_memory_with_format(
         include_bytes!("../../assets/tiles.png"),
         image::ImageFormat::Png
     )
     .unwrap();
-    static ref SPRITESHEET: SpriteArray = spritesheet! [
-        <TextureAtlas imagePath="spritesheet_tiles.png">
-            <SubTexture name="brick_grey.png" x="512" y="256" width="128" height="128"/>
-            <SubTexture name="brick_red.png" x="1024" y="384" width="128" height="128"/>
-            <SubTexture name="cactus_inside.png" x="1024" y="256" width="128" height="128"/>
-            <SubTexture name="cactus_side.png" x="1024" y="128" width="128" height="128"/>
-            <SubTexture name="cactus_top.png" x="1024" y="0" width="128" height="128"/>
-            <SubTexture name="cotton_blue.png" x="896" y="1152" width="128" height="128"/>
-            <SubTexture name="cotton_green.png" x="896" y="1024" width="128" height="128"/>
-            <SubTexture name="cotton_red.png" x="896" y="896" width="128" height="128"/>
-            <SubTexture name="cotton_tan.png" x="896" y="768" width="128" height="128"/>
-            <SubTexture name="dirt.png" x="896" y="640" width="128" height="128"/>
-            <SubTexture name="dirt_grass.png" x="896" y="512" width="128" height="128"/>
-            <SubTexture name="dirt_sand.png" x="896" y="384" width="128" height="128"/>
-            <SubTexture name="dirt_snow.png" x="896" y="256" width="128" height="128"/>
-            <SubTexture name="fence_stone.png" x="896" y="128" width="128" height="128"/>
-            <SubTexture name="fence_wood.png" x="896" y="0" width="128" height="128"/>
-            <SubTexture name="glass.png" x="768" y="1152" width="128" height="128"/>
-            <SubTexture name="glass_frame.png" x="768" y="1024" width="128" height="128"/>
-            <SubTexture name="grass1.png" x="768" y="896" width="128" height="128"/>
-            <SubTexture name="grass2.png" x="768" y="768" width="128" height="128"/>
-            <SubTexture name="grass3.png" x="768" y="640" width="128" height="128"/>
-            <SubTexture name="grass4.png" x="768" y="512" width="128" height="128"/>
-            <SubTexture name="grass_brown.png" x="768" y="384" width="128" height="128"/>
-            <SubTexture name="grass_tan.png" x="768" y="256" width="128" height="128"/>
-            <SubTexture name="grass_top.png" x="768" y="128" width="128" height="128"/>
-            <SubTexture name="gravel_dirt.png" x="768" y="0" width="128" height="128"/>
-            <SubTexture name="gravel_stone.png" x="640" y="1152" width="128" height="128"/>
-            <SubTexture name="greysand.png" x="640" y="1024" width="128" height="128"/>
-            <SubTexture name="greystone.png" x="640" y="896" width="128" height="128"/>
-            <SubTexture name="greystone_ruby.png" x="640" y="768" width="128" height="128"/>
-            <SubTexture name="greystone_ruby_alt.png" x="640" y="640" width="128" height="128"/>
-            <SubTexture name="greystone_sand.png" x="640" y="512" width="128" height="128"/>
-            <SubTexture name="ice.png" x="640" y="384" width="128" height="128"/>
-            <SubTexture name="lava.png" x="640" y="256" width="128" height="128"/>
-            <SubTexture name="leaves.png" x="640" y="128" width="128" height="128"/>
-            <SubTexture name="leaves_orange.png" x="640" y="0" width="128" height="128"/>
-            <SubTexture name="leaves_orange_transparent.png" x="512" y="1152" width="128" height="128"/>
-            <SubTexture name="leaves_transparent.png" x="512" y="1024" width="128" height="128"/>
-            <SubTexture name="mushroom_brown.png" x="512" y="896" width="128" height="128"/>
-            <SubTexture name="mushroom_red.png" x="512" y="768" width="128" height="128"/>
-            <SubTexture name="mushroom_tan.png" x="512" y="640" width="128" height="128"/>
-            <SubTexture name="oven.png" x="512" y="512" width="128" height="128"/>
-            <SubTexture name="redsand.png" x="512" y="384" width="128" height="128"/>
-            <SubTexture name="redstone.png" x="1024" y="512" width="128" height="128"/>
-            <SubTexture name="redstone_emerald.png" x="512" y="128" width="128" height="128"/>
-            <SubTexture name="redstone_emerald_alt.png" x="512" y="0" width="128" height="128"/>
-            <SubTexture name="redstone_sand.png" x="384" y="1152" width="128" height="128"/>
-            <SubTexture name="rock.png" x="384" y="1024" width="128" height="128"/>
-            <SubTexture name="rock_moss.png" x="384" y="896" width="128" height="128"/>
-            <SubTexture name="sand.png" x="384" y="768" width="128" height="128"/>
-            <SubTexture name="snow.png" x="384" y="640" width="128" height="128"/>
-            <SubTexture name="stone.png" x="384" y="512" width="128" height="128"/>
-            <SubTexture name="stone_browniron.png" x="384" y="384" width="128" height="128"/>
-            <SubTexture name="stone_browniron_alt.png" x="384" y="256" width="128" height="128"/>
-            <SubTexture name="stone_coal.png" x="384" y="128" width="128" height="128"/>
-            <SubTexture name="stone_coal_alt.png" x="384" y="0" width="128" height="128"/>
-            <SubTexture name="stone_diamond.png" x="256" y="1152" width="128" height="128"/>
-            <SubTexture name="stone_diamond_alt.png" x="256" y="1024" width="128" height="128"/>
-            <SubTexture name="stone_dirt.png" x="256" y="896" width="128" height="128"/>
-            <SubTexture name="stone_gold.png" x="256" y="768" width="128" height="128"/>
-            <SubTexture name="stone_gold_alt.png" x="256" y="640" width="128" height="128"/>
-            <SubTexture name="stone_grass.png" x="256" y="512" width="128" height="128"/>
-            <SubTexture name="stone_iron.png" x="256" y="384" width="128" height="128"/>
-            <SubTexture name="stone_iron_alt.png" x="256" y="256" width="128" height="128"/>
-            <SubTexture name="stone_sand.png" x="256" y="128" width="128" height="128"/>
-            <SubTexture name="stone_silver.png" x="256" y="0" width="128" height="128"/>
-            <SubTexture name="stone_silver_alt.png" x="128" y="1152" width="128" height="128"/>
-            <SubTexture name="stone_snow.png" x="128" y="1024" width="128" height="128"/>
-            <SubTexture name="table.png" x="128" y="896" width="128" height="128"/>
-            <SubTexture name="track_corner.png" x="128" y="768" width="128" height="128"/>
-            <SubTexture name="track_corner_alt.png" x="128" y="640" width="128" height="128"/>
-            <SubTexture name="track_straight.png" x="128" y="512" width="128" height="128"/>
-            <SubTexture name="track_straight_alt.png" x="128" y="384" width="128" height="128"/>
-            <SubTexture name="trunk_bottom.png" x="128" y="256" width="128" height="128"/>
-            <SubTexture name="trunk_mid.png" x="128" y="128" width="128" height="128"/>
-            <SubTexture name="trunk_side.png" x="128" y="0" width="128" height="128"/>
-            <SubTexture name="trunk_top.png" x="0" y="1152" width="128" height="128"/>
-            <SubTexture name="trunk_white_side.png" x="0" y="1024" width="128" height="128"/>
-            <SubTexture name="trunk_white_top.png" x="0" y="896" width="128" height="128"/>
-            <SubTexture name="water.png" x="0" y="768" width="128" height="128"/>
-            <SubTexture name="wheat_stage1.png" x="0" y="640" width="128" height="128"/>
-            <SubTexture name="wheat_stage2.png" x="0" y="512" width="128" height="128"/>
-            <SubTexture name="wheat_stage3.png" x="0" y="384" width="128" height="128"/>
-            <SubTexture name="wheat_stage4.png" x="0" y="256" width="128" height="128"/>
-            <SubTexture name="wood.png" x="0" y="128" width="128" height="128"/>
-            <SubTexture name="wood_red.png" x="0" y="0" width="128" height="128"/>
-        </TextureAtlas>
-    ];
+    /// Sprites of `TILES`, described by the same atlas XML `LoadedPack` reads
+    static ref SPRITESHEET: SpriteArray = parse_atlas(include_str!("../../assets/tiles.xml"))
+        .unwrap()
+        .1;
     static ref DEFINITIONS: enum_map::EnumMap<BasicId, SolidBlockDefinition> = enum_map! {
         BasicId::Dirt => sprite!("dirt"),
-        BasicId::DirtGrass => SolidBlockDefinition(enum_map! {
+        BasicId::DirtGrass => SolidBlockDefinition::from(enum_map! {
             BlockFace::Up => sprite!("grass_top"),
             BlockFace::Down => sprite!("dirt"),
             _ => sprite!("dirt_grass"),
-        }),
-        BasicId::DirtSand => SolidBlockDefinition(enum_map! {
+        })
+        .with_variants(sprite!("grass_top"), Variant::rotations(sprite!("grass_top")))
+        .with_tint(BlockFace::Up, Tint::Grass),
+        BasicId::DirtSand => SolidBlockDefinition::from(enum_map! {
             BlockFace::Up => sprite!("sand"),
             BlockFace::Down => sprite!("dirt"),
             _ => sprite!("dirt_sand"),
-        }),
-        BasicId::DirtSnow => SolidBlockDefinition(enum_map! {
+        })
+        .with_variants(sprite!("sand"), Variant::rotations(sprite!("sand"))),
+        BasicId::DirtSnow => SolidBlockDefinition::from(enum_map! {
             BlockFace::Up => sprite!("snow"),
             BlockFace::Down => sprite!("dirt"),
             _ => sprite!("dirt_snow"),
-        }),
+        })
+        .with_variants(sprite!("snow"), Variant::rotations(sprite!("snow"))),
         BasicId::WoodRed => sprite!("wood_red"),
         BasicId::Wood => sprite!("wood"),
         BasicId::BrickGrey => sprite!("brick_grey"),
@@ -167,17 +211,135 @@ lazy_static! {
         BasicId::RedSand => sprite!("redsand"),
         BasicId::GreyStone => sprite!("greystone"),
         BasicId::GreySand => sprite!("greysand"),
+        BasicId::Trunk => SolidBlockDefinition::with_states(|axis| {
+            let ends: &[BlockFace] = match axis {
+                TrunkAxis::Y => &[BlockFace::Up, BlockFace::Down],
+                TrunkAxis::X => &[BlockFace::East, BlockFace::West],
+                TrunkAxis::Z => &[BlockFace::North, BlockFace::South],
+            };
+            SolidBlockDefinition::from(|face| {
+                if ends.contains(&face) {
+                    sprite!("trunk_top")
+                } else {
+                    sprite!("trunk_side")
+                }
+            })
+        }),
+        BasicId::Wheat => SolidBlockDefinition::with_states(|stage| match stage {
+            WheatStage::Stage1 => sprite!("wheat_stage1"),
+            WheatStage::Stage2 => sprite!("wheat_stage2"),
+            WheatStage::Stage3 => sprite!("wheat_stage3"),
+            WheatStage::Stage4 => sprite!("wheat_stage4"),
+        })
+        .with_kind(BlockKind::Cross),
+        BasicId::StoneCoal => ore!("stone_coal"),
+        BasicId::StoneIron => ore!("stone_iron"),
+        BasicId::StoneGold => ore!("stone_gold"),
+        BasicId::StoneDiamond => ore!("stone_diamond"),
+        BasicId::GreyStoneRuby => ore!("greystone_ruby"),
+        BasicId::RedStoneEmerald => ore!("redstone_emerald"),
+        BasicId::Glass => SolidBlockDefinition::new_simple_block(sprite!("glass"))
+            .with_kind(BlockKind::Transparent),
+        BasicId::Leaves => SolidBlockDefinition::new_simple_block(sprite!("leaves_transparent"))
+            .with_kind(BlockKind::Transparent)
+            .tinted(Tint::Foliage),
+        BasicId::Grass => SolidBlockDefinition::with_states(|style| match style {
+            GrassStyle::Short => sprite!("grass1"),
+            GrassStyle::Medium => sprite!("grass2"),
+            GrassStyle::Tall => sprite!("grass3"),
+            GrassStyle::Flowering => sprite!("grass4"),
+        })
+        .with_kind(BlockKind::Cross)
+        .tinted(Tint::Grass),
+        BasicId::Mushroom => SolidBlockDefinition::with_states(|color| match color {
+            MushroomColor::Brown => sprite!("mushroom_brown"),
+            MushroomColor::Red => sprite!("mushroom_red"),
+            MushroomColor::Tan => sprite!("mushroom_tan"),
+        })
+        .with_kind(BlockKind::Cross),
+        BasicId::Water => SolidBlockDefinition::animated(scrolling(0))
+            .with_kind(BlockKind::Liquid),
+        BasicId::Lava => SolidBlockDefinition::animated(scrolling(1))
+            .with_kind(BlockKind::Liquid),
+        BasicId::GlassFrame => SolidBlockDefinition::new_simple_block(sprite!("glass_frame"))
+            .with_connected(sprite!("glass_frame"), connected(0))
+            .with_kind(BlockKind::Transparent),
     };
+    /// Blocks `random_blocks` returns, the opaque ones
+    static ref RANDOM: Vec<BasicId> = BasicId::iter()
+        .filter(|&id| DEFINITIONS[id].kind() == BlockKind::Opaque)
+        .collect();
+}
+
+impl BasicPack {
+    /// Texture called `name` in the tile sheet, as `sprite!` names them
+    pub fn sprite(name: &str) -> Option<TextureIndex> {
+        SPRITESHEET.get(&format!("{}.png", name))
+    }
+}
+
+/// Image of the sprite called `name`, a transparent pixel if there is none
+/// (which `BasicPack::validate` reports)
+fn sprite_image(name: &'static str) -> RgbaImage {
+    match SPRITESHEET.sprites().get(SPRITESHEET[name].0 as usize) {
+        Some(sprite) => sprite.crop_image(&TILES).into_rgba8(),
+        None => RgbaImage::new(1, 1),
+    }
 }
 
 impl Pack for BasicPack {
     type Id = BasicId;
 
+    /// Atlas sprites, then the extra frames of the `SCROLLING` sprites, each
+    /// shifted down a further fraction of its height, then the joined tiles of
+    /// the `CONNECTED` sprites
     fn get_textures() -> Vec<glium::texture::RawImage2d<'static, u8>> {
-        SPRITESHEET.get_image_array(&TILES)
+        let mut textures = SPRITESHEET.get_image_array(&TILES);
+        for name in &SCROLLING {
+            let sprite = sprite_image(name);
+            let (width, height) = sprite.dimensions();
+            for frame in 1..SCROLL_FRAMES {
+                let shift = height - (height * frame as u32 / SCROLL_FRAMES as u32);
+                let scrolled = image::RgbaImage::from_fn(width, height, |x, y| {
+                    *sprite.get_pixel(x, (y + shift) % height)
+                });
+                textures.push(glium::texture::RawImage2d::from_raw_rgba(
+                    scrolled.into_raw(),
+                    (width, height),
+                ));
+            }
+        }
+        for name in &CONNECTED {
+            let sprite = sprite_image(name);
+            let border = frame_border(sprite.width());
+            for tile in connected_tiles(&sprite, border).into_iter().skip(1) {
+                let dim = tile.dimensions();
+                textures.push(glium::texture::RawImage2d::from_raw_rgba(
+                    tile.into_raw(),
+                    dim,
+                ));
+            }
+        }
+        textures
     }
 
     fn get_map() -> &'static enum_map::EnumMap<Self::Id, SolidBlockDefinition> {
-        &*DEFINITIONS
+        &DEFINITIONS
+    }
+
+    fn sprite_names() -> Vec<(String, TextureIndex)> {
+        SPRITESHEET
+            .names()
+            .map(|(name, texture)| (name.to_string(), texture))
+            .collect()
+    }
+
+    /// Also checks the tile sheet, including the `sprite!` names it lacks
+    fn validate() -> Report {
+        let layers = Self::get_textures().len();
+        let blocks = Self::get_map()
+            .iter()
+            .map(|(id, definition)| (id.to_string(), definition));
+        validate(Some((&SPRITESHEET, TILES.dimensions())), layers, blocks)
     }
 }
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+
+use enum_map::EnumMap;
+use glium::texture::RawImage2d;
+use image::RgbaImage;
+
+use super::{
+    array::{ArrayOptions, TextureArray},
+    loader::LoadedPack,
+    Pack, SimpleBlockId,
+};
+use crate::{
+    BlockProperties, BlockTag, Registry, RegistryError, RegistryId, SolidBlockDefinition,
+    TextureIndex,
+};
+
+/// Texture layers and named blocks of one pack, to be composed with others
+pub struct PackSource {
+    layers: Vec<RgbaImage>,
+    sprites: Vec<(String, TextureIndex)>,
+    blocks: Vec<(BlockProperties, SolidBlockDefinition)>,
+}
+
+impl PackSource {
+    /// Pack of `textures` (as `Pack::get_textures` returns them), the `sprites`
+    /// naming some of them and the blocks of `registry`
+    pub fn new(
+        textures: Vec<RawImage2d<'static, u8>>,
+        sprites: Vec<(String, TextureIndex)>,
+        registry: &Registry,
+    ) -> Self {
+        let blocks = registry
+            .ids()
+            .map(|id| {
+                let properties = registry.properties(id).unwrap().clone();
+                (properties, registry.definitions()[id].clone())
+            })
+            .collect();
+        Self {
+            layers: textures.into_iter().map(to_image).collect(),
+            sprites,
+            blocks,
+        }
+    }
+
+    /// Compiled-in pack `P`, its blocks named after their id in snake case as
+    /// block lists name them and tagged like its `SimpleBlockId` picks them
+    pub fn of<P: Pack>() -> Self
+    where
+        P::Id: SimpleBlockId,
+    {
+        let blocks = P::get_map()
+            .iter()
+            .map(|(id, definition)| {
+                let mut tags = Vec::new();
+                if id == P::Id::get_simple_block() {
+                    tags.push(BlockTag::Simple);
+                }
+                if id == P::Id::get_simple_top_block() {
+                    tags.push(BlockTag::Top);
+                }
+                let name = snake_case(&id.to_string());
+                (BlockProperties { name, tags }, definition.clone())
+            })
+            .collect();
+        Self {
+            layers: P::get_textures().into_iter().map(to_image).collect(),
+            sprites: P::sprite_names(),
+            blocks,
+        }
+    }
+}
+
+impl From<&LoadedPack> for PackSource {
+    fn from(pack: &LoadedPack) -> Self {
+        let sprites = pack
+            .sprites()
+            .names()
+            .map(|(name, texture)| (name.to_string(), texture))
+            .collect();
+        Self::new(pack.textures(), sprites, pack.registry())
+    }
+}
+
+/// Several packs merged into one texture array and block registry, like a
+/// `LoadedPack` with blocks identified by `RegistryId`
+///
+/// Packs are composed in increasing priority, a base pack first and then its
+/// overlays. A sprite named like one of an earlier pack replaces its image in
+/// place, so the blocks showing it change too, and a block named like an
+/// earlier one replaces its definition and tags under the same id. Anything
+/// else is appended, with the textures of each pack remapped to the composed
+/// layers.
+///
+/// Only the named layer is replaced: layers a pack derives from a sprite, like
+/// the scroll frames of `water.png` or the connected tiles of `glass_frame.png`
+/// in `BasicPack`, have no name and keep showing the base image. Overlays
+/// wanting those to match override the blocks using them as well.
+pub struct ComposedPack {
+    layers: Vec<RgbaImage>,
+    sprites: HashMap<String, TextureIndex>,
+    registry: Registry,
+    /// Composed layer of every layer of each pack, in composition order
+    remaps: Vec<Vec<TextureIndex>>,
+}
+
+impl ComposedPack {
+    pub fn compose(packs: impl IntoIterator<Item = PackSource>) -> Result<Self, RegistryError> {
+        let mut layers: Vec<RgbaImage> = Vec::new();
+        let mut sprites = HashMap::new();
+        let mut blocks: Vec<(BlockProperties, SolidBlockDefinition)> = Vec::new();
+        let mut remaps = Vec::new();
+        for pack in packs {
+            let names: HashMap<_, _> = pack
+                .sprites
+                .into_iter()
+                .map(|(name, texture)| (texture, name))
+                .collect();
+            let mut remap = Vec::with_capacity(pack.layers.len());
+            for (index, layer) in pack.layers.into_iter().enumerate() {
+                let name = names.get(&TextureIndex(index as u16));
+                match name.and_then(|name| sprites.get(name)) {
+                    Some(&TextureIndex(existing)) => {
+                        layers[existing as usize] = layer;
+                        remap.push(TextureIndex(existing));
+                    }
+                    None => {
+                        let texture = TextureIndex(layers.len() as u16);
+                        layers.push(layer);
+                        if let Some(name) = name {
+                            sprites.insert(name.clone(), texture);
+                        }
+                        remap.push(texture);
+                    }
+                }
+            }
+            for (properties, definition) in pack.blocks {
+                let definition = definition.remapped(|texture| {
+                    remap
+                        .get(texture.0 as usize)
+                        .copied()
+                        .unwrap_or(TextureIndex::MISSING)
+                });
+                match blocks
+                    .iter_mut()
+                    .find(|(existing, _)| existing.name == properties.name)
+                {
+                    Some(block) => *block = (properties, definition),
+                    None => blocks.push((properties, definition)),
+                }
+            }
+            remaps.push(remap);
+        }
+        let mut registry = Registry::default();
+        for (properties, definition) in blocks {
+            registry.register(properties, definition)?;
+        }
+        Ok(Self {
+            layers,
+            sprites,
+            registry,
+            remaps,
+        })
+    }
+
+    /// One image per composed layer, ready for a texture array: packs drawn at
+    /// another resolution are resampled to the largest layer, see `TextureArray`
+    pub fn textures(&self) -> Vec<RawImage2d<'static, u8>> {
+        let layers = self.layers.iter().map(to_raw).collect();
+        let array = TextureArray::new(layers, &ArrayOptions::default());
+        array.level(0).iter().map(to_raw).collect()
+    }
+
+    pub fn definitions(&self) -> &EnumMap<RegistryId, SolidBlockDefinition> {
+        self.registry.definitions()
+    }
+
+    /// Composed texture of the sprite called `name`, with or without `.png`
+    pub fn sprite(&self, name: &str) -> Option<TextureIndex> {
+        self.sprites
+            .get(name)
+            .or_else(|| self.sprites.get(&format!("{}.png", name)))
+            .copied()
+    }
+
+    /// Composed layer of `texture` of the `pack`th composed pack
+    pub fn remap(&self, pack: usize, texture: TextureIndex) -> Option<TextureIndex> {
+        self.remaps.get(pack)?.get(texture.0 as usize).copied()
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Keep only the block registry, once `textures` are taken
+    pub fn into_registry(self) -> Registry {
+        self.registry
+    }
+}
+
+fn to_raw(layer: &RgbaImage) -> RawImage2d<'static, u8> {
+    RawImage2d::from_raw_rgba(layer.clone().into_raw(), layer.dimensions())
+}
+
+fn to_image(layer: RawImage2d<'static, u8>) -> RgbaImage {
+    RgbaImage::from_raw(layer.width, layer.height, layer.data.into_owned())
+        .expect("layer data does not match its size")
+}
+
+/// `DirtGrass` as `dirt_grass`
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (index, c) in name.char_indices() {
+        if c.is_uppercase() && index > 0 {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        packs::{array::check_uniform, basic::*, loader::parse_atlas},
+        BlockFace,
+    };
+
+    use super::*;
+
+    fn layer(shade: u8) -> RawImage2d<'static, u8> {
+        RawImage2d::from_raw_rgba(vec![shade; 4 * 4], (2, 2))
+    }
+
+    #[test]
+    fn overlays_override_by_name() {
+        let source = |shade, sprites: &[(&str, u16)], blocks: &str| {
+            let sprites: Vec<_> = sprites
+                .iter()
+                .map(|&(name, layer)| (name.to_string(), TextureIndex(layer)))
+                .collect();
+            let lookup = sprites.clone();
+            let registry = Registry::parse(blocks, |name| {
+                lookup
+                    .iter()
+                    .find(|(sprite, _)| sprite == name)
+                    .map(|&(_, texture)| texture)
+            })
+            .unwrap();
+            let textures = (0..sprites.len() as u8).map(|i| layer(shade + i)).collect();
+            PackSource::new(textures, sprites, &registry)
+        };
+        let base = source(
+            0,
+            &[("stone", 0), ("dirt", 1)],
+            "stone opaque all=stone simple\ndirt opaque all=dirt",
+        );
+        let project = source(
+            100,
+            &[("dirt", 0), ("ore", 1)],
+            "ore opaque all=ore\ndirt opaque all=ore side=dirt top",
+        );
+        let pack = ComposedPack::compose(vec![base, project]).unwrap();
+        let textures = pack.textures();
+        assert_eq!(textures.len(), 3);
+        assert_eq!(textures[1].data[0], 100);
+        assert_eq!(pack.sprite("ore"), Some(TextureIndex(2)));
+        assert_eq!(pack.remap(1, TextureIndex(0)), Some(TextureIndex(1)));
+        let registry = pack.registry();
+        assert_eq!(registry.len(), 3);
+        let dirt = registry.id("dirt").unwrap();
+        assert_eq!(dirt, RegistryId(1));
+        assert_eq!(pack.definitions()[dirt][BlockFace::Up], TextureIndex(2));
+        assert_eq!(pack.definitions()[dirt][BlockFace::North], TextureIndex(1));
+        assert_eq!(registry.tagged(BlockTag::Top), Some(dirt));
+    }
+
+    #[test]
+    fn basic_pack_takes_overlays() {
+        let (_, sprites) = parse_atlas(
+            r#"<TextureAtlas imagePath="overlay.png">
+                <SubTexture name="dirt.png" x="0" y="0" width="2" height="2"/>
+            </TextureAtlas>"#,
+        )
+        .unwrap();
+        let overlay = LoadedPack::new(
+            image::DynamicImage::new_rgba8(2, 2),
+            sprites,
+            "dirt_grass opaque all=dirt top",
+        )
+        .unwrap();
+        let pack =
+            ComposedPack::compose(vec![PackSource::of::<BasicPack>(), (&overlay).into()]).unwrap();
+        let dirt = BasicPack::sprite("dirt").unwrap();
+        let textures = pack.textures();
+        assert_eq!(textures.len(), BasicPack::get_textures().len());
+        // The 2x2 overlay sprite is scaled up to the basic tiles
+        let size = BasicPack::get_textures()[0].width;
+        assert_eq!(check_uniform(&textures).unwrap(), Some((size, size)));
+        assert!(textures[dirt.0 as usize].data.iter().all(|&byte| byte == 0));
+        let registry = pack.registry();
+        assert_eq!(registry.len(), BasicPack::get_map().len());
+        let grass = registry.id("dirt_grass").unwrap();
+        assert_eq!(pack.definitions()[grass][BlockFace::Up], dirt);
+        assert_eq!(registry.tagged(BlockTag::Simple), registry.id("dirt"));
+        let water = registry.id("water").unwrap();
+        assert_eq!(
+            pack.definitions()[water].animations(),
+            BasicPack::get_map()[BasicId::Water].animations()
+        );
+    }
+
+    #[test]
+    fn overrides_leave_derived_layers() {
+        let (_, sprites) = parse_atlas(
+            r#"<TextureAtlas imagePath="overlay.png">
+                <SubTexture name="water.png" x="0" y="0" width="2" height="2"/>
+            </TextureAtlas>"#,
+        )
+        .unwrap();
+        let overlay = LoadedPack::new(image::DynamicImage::new_rgba8(2, 2), sprites, "").unwrap();
+        let pack =
+            ComposedPack::compose(vec![PackSource::of::<BasicPack>(), (&overlay).into()]).unwrap();
+        let textures = pack.textures();
+        let base = BasicPack::get_textures();
+        let frames = &BasicPack::get_map()[BasicId::Water].animations()[0].frames;
+        let (first, scrolled) = (frames[0].0 as usize, frames[1].0 as usize);
+        assert!(textures[first].data.iter().all(|&byte| byte == 0));
+        assert_eq!(textures[scrolled].data, base[scrolled].data);
+    }
+}
@@ -0,0 +1,326 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use enum_map::EnumMap;
+use image::{DynamicImage, GenericImageView};
+
+use super::{
+    utils::{SpriteArray, SpriteDefinition},
+    validation::{validate, Report},
+};
+use crate::{Registry, RegistryError, RegistryId, SolidBlockDefinition, TextureIndex};
+
+/// Why a pack could not be loaded
+#[derive(Debug)]
+pub enum PackError {
+    Io(PathBuf, std::io::Error),
+    /// Malformed atlas XML, on a (1-based) line
+    Atlas {
+        line: usize,
+        message: String,
+    },
+    Image(PathBuf, image::ImageError),
+    /// Sprite reaching past the atlas image
+    OutOfImage(String),
+    Blocks(RegistryError),
+}
+
+impl Display for PackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackError::Io(path, err) => write!(f, "cannot read {}: {}", path.display(), err),
+            PackError::Atlas { line, message } => write!(f, "atlas line {}: {}", line, message),
+            PackError::Image(path, err) => write!(f, "cannot load {}: {}", path.display(), err),
+            PackError::OutOfImage(name) => {
+                write!(f, "sprite `{}` lies outside the atlas image", name)
+            }
+            PackError::Blocks(err) => write!(f, "block list: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+impl From<RegistryError> for PackError {
+    fn from(err: RegistryError) -> Self {
+        PackError::Blocks(err)
+    }
+}
+
+/// Attributes of one XML tag, in source order
+type Attributes<'a> = Vec<(&'a str, &'a str)>;
+
+/// Split the tags of a TexturePacker atlas into `(line, name, attributes)`,
+/// skipping the prolog, comments and closing tags
+fn atlas_tags(source: &str) -> Result<Vec<(usize, &str, Attributes<'_>)>, PackError> {
+    let mut tags = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find('<') {
+        let line = source[..source.len() - rest.len() + start]
+            .matches('\n')
+            .count()
+            + 1;
+        let error = |message: &str| PackError::Atlas {
+            line,
+            message: message.into(),
+        };
+        rest = &rest[start + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            let end = comment
+                .find("-->")
+                .ok_or_else(|| error("unclosed comment"))?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        let end = rest.find('>').ok_or_else(|| error("unclosed tag"))?;
+        let tag = rest[..end].trim_end_matches('/');
+        rest = &rest[end + 1..];
+        if tag.starts_with('?') || tag.starts_with('/') {
+            continue;
+        }
+        let (name, mut body) = tag.split_at(tag.find(char::is_whitespace).unwrap_or(tag.len()));
+        let mut attributes = Vec::new();
+        loop {
+            body = body.trim_start();
+            if body.is_empty() {
+                break;
+            }
+            let (key, value) = body
+                .split_once("=\"")
+                .ok_or_else(|| error("expected key=\"value\""))?;
+            let end = value.find('"').ok_or_else(|| error("unclosed attribute"))?;
+            attributes.push((key.trim(), &value[..end]));
+            body = &value[end + 1..];
+        }
+        tags.push((line, name, attributes));
+    }
+    Ok(tags)
+}
+
+/// Attribute value with the predefined XML entities replaced, `&amp;` last so
+/// `&amp;lt;` stays `&lt;`
+fn unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Parse a TexturePacker `<TextureAtlas>` XML file into its `imagePath` and
+/// sprites, numbered in file order like `spritesheet!` does
+pub fn parse_atlas(source: &str) -> Result<(String, SpriteArray), PackError> {
+    let mut image_path = None;
+    let mut sprites = Vec::new();
+    for (line, name, attributes) in atlas_tags(source)? {
+        let error = |message: String| PackError::Atlas { line, message };
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|&(_, value)| value)
+                .ok_or_else(|| error(format!("missing `{}`", key)))
+        };
+        match name {
+            "TextureAtlas" => image_path = Some(unescape(attribute("imagePath")?)),
+            "SubTexture" => {
+                if let Ok("y") | Ok("true") = attribute("rotated") {
+                    return Err(error("rotated sprites are not supported".into()));
+                }
+                let number = |key| {
+                    attribute(key)?
+                        .parse::<u32>()
+                        .map_err(|_| error(format!("`{}` is not a number", key)))
+                };
+                let sprite = SpriteDefinition::from((
+                    number("x")?,
+                    number("y")?,
+                    number("width")?,
+                    number("height")?,
+                ));
+                sprites.push((unescape(attribute("name")?), sprite));
+            }
+            _ => return Err(error(format!("unexpected <{}>", name))),
+        }
+    }
+    let image_path = image_path.ok_or(PackError::Atlas {
+        line: 1,
+        message: "no <TextureAtlas>".into(),
+    })?;
+    Ok((image_path, sprites.into_iter().collect()))
+}
+
+/// Pack read from disk at runtime: a TexturePacker atlas, the image it names
+/// and a block list (see `Registry`) using the atlas sprite names
+///
+/// Provides what a compiled-in `Pack` does, `textures` for `get_textures` and
+/// `definitions` for `get_map`, with blocks identified by `RegistryId`.
+/// Sprite names may leave out their `.png` extension in the block list.
+pub struct LoadedPack {
+    image: DynamicImage,
+    sprites: SpriteArray,
+    registry: Registry,
+}
+
+impl LoadedPack {
+    /// Load the atlas XML at `atlas`, its image (relative to the XML file) and
+    /// the block list at `blocks`
+    pub fn load(atlas: impl AsRef<Path>, blocks: impl AsRef<Path>) -> Result<Self, PackError> {
+        let atlas = atlas.as_ref();
+        let blocks = blocks.as_ref();
+        let read = |path: &Path| {
+            std::fs::read_to_string(path).map_err(|err| PackError::Io(path.into(), err))
+        };
+        let (image_path, sprites) = parse_atlas(&read(atlas)?)?;
+        let image_path = atlas.with_file_name(image_path);
+        let image = image::open(&image_path).map_err(|err| PackError::Image(image_path, err))?;
+        Self::new(image, sprites, &read(blocks)?)
+    }
+
+    /// Assemble a pack from an atlas image, its sprites and a block list
+    pub fn new(image: DynamicImage, sprites: SpriteArray, blocks: &str) -> Result<Self, PackError> {
+        let (width, height) = image.dimensions();
+        if let Some(index) = sprites
+            .sprites()
+            .iter()
+            .position(|sprite| !sprite.fits(width, height))
+        {
+            let name = sprites
+                .names()
+                .find(|(_, texture)| texture.0 as usize == index)
+                .map_or_else(|| index.to_string(), |(name, _)| name.to_string());
+            return Err(PackError::OutOfImage(name));
+        }
+        let registry = Registry::parse(blocks, |name| sprite_index(&sprites, name))?;
+        Ok(Self {
+            image,
+            sprites,
+            registry,
+        })
+    }
+
+    /// One image per sprite, ready for a texture array
+    pub fn textures(&self) -> Vec<glium::texture::RawImage2d<'static, u8>> {
+        self.sprites.get_image_array(&self.image)
+    }
+
+    pub fn definitions(&self) -> &EnumMap<RegistryId, SolidBlockDefinition> {
+        self.registry.definitions()
+    }
+
+    /// Texture index of the sprite called `name`, with or without `.png`
+    pub fn sprite(&self, name: &str) -> Option<TextureIndex> {
+        sprite_index(&self.sprites, name)
+    }
+
+    pub fn sprites(&self) -> &SpriteArray {
+        &self.sprites
+    }
+
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Problems with the atlas and the registered blocks, see `validate`
+    pub fn validate(&self) -> Report {
+        let blocks = self.registry.ids().map(|id| {
+            let name = self.registry.properties(id).unwrap().name.clone();
+            (name, &self.definitions()[id])
+        });
+        validate(
+            Some((&self.sprites, self.image.dimensions())),
+            self.sprites.sprites().len(),
+            blocks,
+        )
+    }
+
+    /// Keep only the block registry, once `textures` are taken
+    pub fn into_registry(self) -> Registry {
+        self.registry
+    }
+}
+
+fn sprite_index(sprites: &SpriteArray, name: &str) -> Option<TextureIndex> {
+    sprites
+        .get(name)
+        .or_else(|| sprites.get(&format!("{}.png", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use crate::{
+        packs::{basic::*, Pack},
+        BlockFace,
+    };
+
+    use super::*;
+
+    #[test]
+    fn loads_basic_tiles() {
+        let pack = LoadedPack::load("assets/tiles.xml", "assets/blocks.txt").unwrap();
+        let loaded = pack.textures();
+        // The embedded pack appends the frames of its animations
+        let embedded = BasicPack::get_textures();
+        assert!(loaded.len() < embedded.len());
+        for (loaded, embedded) in loaded.iter().zip(&embedded) {
+            assert_eq!(
+                (loaded.width, loaded.height),
+                (embedded.width, embedded.height)
+            );
+            assert_eq!(loaded.data, embedded.data);
+        }
+        assert!(pack.validate().is_ok(), "{}", pack.validate());
+        let grass = pack.registry().id("dirt_grass").unwrap();
+        for face in BlockFace::iter() {
+            assert_eq!(
+                pack.definitions()[grass][face],
+                BasicPack::get_map()[BasicId::DirtGrass][face]
+            );
+        }
+    }
+
+    #[test]
+    fn reports_errors() {
+        let image = DynamicImage::new_rgba8(16, 16);
+        let atlas = |source| parse_atlas(source).map(|(_, sprites)| sprites);
+        let sprites = atlas(
+            r#"<TextureAtlas imagePath="a.png">
+                <SubTexture name="a.png" x="0" y="0" width="16" height="16"/>
+            </TextureAtlas>"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            LoadedPack::new(image.clone(), sprites, "a opaque all=b"),
+            Err(PackError::Blocks(RegistryError::Parse { line: 1, .. }))
+        ));
+        let sprites = atlas(
+            r#"<TextureAtlas imagePath="a.png">
+                <SubTexture name="a.png" x="8" y="0" width="16" height="16"/>
+            </TextureAtlas>"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            LoadedPack::new(image.clone(), sprites, ""),
+            Err(PackError::OutOfImage(name)) if name == "a.png"
+        ));
+        let sprites = atlas(
+            r#"<TextureAtlas imagePath="a.png">
+                <SubTexture name="a.png" x="4294967295" y="0" width="16" height="16"/>
+            </TextureAtlas>"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            LoadedPack::new(image, sprites, ""),
+            Err(PackError::OutOfImage(name)) if name == "a.png"
+        ));
+        assert!(matches!(
+            atlas("<TextureAtlas imagePath=\"a.png\">\n<SubTexture name=\"a.png\" x=\"0\"/>"),
+            Err(PackError::Atlas { line: 2, .. })
+        ));
+    }
+}
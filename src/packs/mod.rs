@@ -1,8 +1,14 @@
 use enum_map::EnumMap;
+use rand::Rng;
 
-use crate::{BlockId, SolidBlockDefinition};
+use crate::{BlockId, SolidBlockDefinition, TextureIndex};
 
+pub mod array;
+pub mod atlas;
 pub mod basic;
+pub mod compose;
+pub mod loader;
+pub mod validation;
 
 #[macro_use]
 pub mod utils;
@@ -12,6 +18,22 @@ pub trait Pack {
 
     fn get_textures() -> Vec<glium::texture::RawImage2d<'static, u8>>;
     fn get_map() -> &'static EnumMap<Self::Id, SolidBlockDefinition>;
+
+    /// Names of the texture layers that have one, which `compose` matches
+    /// overriding sprites by
+    fn sprite_names() -> Vec<(String, TextureIndex)> {
+        Vec::new()
+    }
+
+    /// Problems with the block definitions, see `validation::validate`; packs
+    /// built from an atlas also check its sprites
+    fn validate() -> validation::Report {
+        let layers = Self::get_textures().len();
+        let blocks = Self::get_map()
+            .iter()
+            .map(|(id, definition)| (id.to_string(), definition));
+        validation::validate(None, layers, blocks)
+    }
 }
 
 pub trait SimpleBlockId: BlockId {
@@ -19,5 +41,62 @@ pub trait SimpleBlockId: BlockId {
 
     fn get_simple_top_block() -> Self;
 
-    fn get_random_block() -> Self;
+    /// Blocks `get_random_block` picks from, the opaque ones; never empty
+    fn random_blocks() -> &'static [Self];
+
+    /// Any of `random_blocks`, picked with `rng` so seeded generators stay
+    /// reproducible
+    fn get_random_block(rng: &mut impl Rng) -> Self {
+        let blocks = Self::random_blocks();
+        blocks[rng.gen_range(0..blocks.len())]
+    }
+}
+
+/// Blocks the simple generators build from, picked at runtime
+///
+/// `SimpleBlocks::of` takes them from a `SimpleBlockId`, `Registry::simple_blocks`
+/// from the tags of a block registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleBlocks<Id: BlockId> {
+    /// Filler, see `SimpleBlockId::get_simple_block`
+    pub simple: Id,
+    /// Surface, see `SimpleBlockId::get_simple_top_block`
+    pub top: Id,
+    random: Vec<Id>,
+}
+
+impl<Id: BlockId> SimpleBlocks<Id> {
+    /// Blocks `random` picks from `random`, or only `simple` if that is empty
+    pub fn new(simple: Id, top: Id, mut random: Vec<Id>) -> Self {
+        if random.is_empty() {
+            random.push(simple);
+        }
+        Self {
+            simple,
+            top,
+            random,
+        }
+    }
+
+    /// The blocks of `SimpleBlockId` `Id`
+    pub fn of() -> Self
+    where
+        Id: SimpleBlockId,
+    {
+        Self::new(
+            Id::get_simple_block(),
+            Id::get_simple_top_block(),
+            Id::random_blocks().to_vec(),
+        )
+    }
+
+    /// Blocks `random` picks from, never empty
+    pub fn random_blocks(&self) -> &[Id] {
+        &self.random
+    }
+
+    /// Any of `random_blocks`, see `SimpleBlockId::get_random_block`
+    pub fn random(&self, rng: &mut impl Rng) -> Id {
+        self.random[rng.gen_range(0..self.random.len())]
+    }
 }
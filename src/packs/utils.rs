@@ -1,7 +1,7 @@
-use std::{collections::HashMap, iter::FromIterator, ops::Index};
+use std::{collections::HashMap, iter::FromIterator, ops::Index, sync::Mutex};
 
 use glium::texture::RawImage2d;
-use image::{DynamicImage, GenericImageView};
+use image::{DynamicImage, GenericImageView, RgbaImage};
 
 use crate::TextureIndex;
 
@@ -23,6 +23,14 @@ impl SpriteDefinition {
         } = self;
         image.crop_imm(x, y, width, height)
     }
+
+    /// Whether the sprite lies inside an image of `width` by `height`, even
+    /// when its edges are past `u32::MAX`
+    pub fn fits(&self, width: u32, height: u32) -> bool {
+        let inside =
+            |start: u32, size: u32, limit| start.checked_add(size).is_some_and(|end| end <= limit);
+        inside(self.x, self.width, width) && inside(self.y, self.height, height)
+    }
 }
 
 impl From<(u32, u32, u32, u32)> for SpriteDefinition {
@@ -53,13 +61,44 @@ macro_rules! spritesheet {
 #[derive(Default)]
 pub struct SpriteArray {
     tiles: Vec<SpriteDefinition>,
-    map: HashMap<&'static str, TextureIndex>,
+    map: HashMap<String, TextureIndex>,
+    /// Names indexed with but not found, see `missing`
+    missing: Mutex<Vec<String>>,
 }
 
 impl SpriteArray {
+    /// Index of the sprite called `name`, file extension included
+    pub fn get(&self, name: &str) -> Option<TextureIndex> {
+        self.map.get(name).copied()
+    }
+
+    /// Sprite names with their texture index, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = (&str, TextureIndex)> {
+        self.map.iter().map(|(name, &index)| (name.as_str(), index))
+    }
+
+    /// Name of the sprite at `index`
+    pub fn name(&self, index: TextureIndex) -> Option<&str> {
+        self.map
+            .iter()
+            .find(|(_, &texture)| texture == index)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Names indexing this array found no sprite for, in lookup order
+    pub fn missing(&self) -> Vec<String> {
+        self.missing.lock().unwrap().clone()
+    }
+
+    /// Sprite areas, in texture index order
+    pub fn sprites(&self) -> &[SpriteDefinition] {
+        &self.tiles
+    }
+
+    /// One image per sprite at its atlas size, `TextureArray` evens out mixed sizes
     pub fn get_image_array(
         &self,
-        origin: &'static DynamicImage,
+        origin: &DynamicImage,
     ) -> Vec<glium::texture::RawImage2d<'static, u8>> {
         self.tiles
             .iter()
@@ -72,22 +111,91 @@ impl SpriteArray {
     }
 }
 
+/// Sprite named in code, as `sprite!` looks them up; an unknown name gives
+/// `TextureIndex::MISSING` and is remembered for `packs::validation` rather than
+/// panicking while a pack builds its definitions
 impl Index<&'static str> for SpriteArray {
     type Output = TextureIndex;
 
     fn index(&self, index: &'static str) -> &Self::Output {
-        &self.map[index]
+        match self.map.get(index) {
+            Some(texture) => texture,
+            None => {
+                self.missing.lock().unwrap().push(index.to_string());
+                &TextureIndex::MISSING
+            }
+        }
     }
 }
 
-impl FromIterator<(&'static str, SpriteDefinition)> for SpriteArray {
-    fn from_iter<T: IntoIterator<Item = (&'static str, SpriteDefinition)>>(iter: T) -> Self {
+impl<N: Into<String>> FromIterator<(N, SpriteDefinition)> for SpriteArray {
+    fn from_iter<T: IntoIterator<Item = (N, SpriteDefinition)>>(iter: T) -> Self {
         let mut ret: Self = Default::default();
         for (name, def) in iter {
             let idx = ret.tiles.len() as u16;
             ret.tiles.push(def);
-            ret.map.insert(name, TextureIndex(idx));
+            ret.map.insert(name.into(), TextureIndex(idx));
         }
         ret
     }
 }
+
+/// The 16 tiles of a connected texture made from a framed `tile`, in the order
+/// `SolidBlockDefinition::with_connected` takes them
+///
+/// Each joined side loses the `border` pixels wide frame strip along it, filled
+/// with pixels from the middle of the tile; frame corners only go when both
+/// their sides are joined. Mask bits 0 to 3 stand for the left, right, top and
+/// bottom sides. A `border` wider than half the tile is clamped to half of it.
+pub fn connected_tiles(tile: &RgbaImage, border: u32) -> Vec<RgbaImage> {
+    let (width, height) = tile.dimensions();
+    let (border_x, border_y) = (border.min(width / 2), border.min(height / 2));
+    (0..16u8)
+        .map(|mask| {
+            let joined = |bit: u8| mask & 1 << bit != 0;
+            RgbaImage::from_fn(width, height, |x, y| {
+                let sides = [
+                    x < border_x,
+                    x >= width - border_x,
+                    y < border_y,
+                    y >= height - border_y,
+                ];
+                let on_frame = sides.iter().any(|&side| side);
+                let all_joined = (0..4).all(|bit| !sides[bit as usize] || joined(bit));
+                if !on_frame || !all_joined {
+                    return *tile.get_pixel(x, y);
+                }
+                let x = if sides[0] {
+                    x + width / 2
+                } else if sides[1] {
+                    x - width / 2
+                } else {
+                    x
+                };
+                let y = if sides[2] {
+                    y + height / 2
+                } else if sides[3] {
+                    y - height / 2
+                } else {
+                    y
+                };
+                *tile.get_pixel(x, y)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connected_tiles_clamp_border() {
+        let tile = RgbaImage::from_fn(4, 2, |x, y| image::Rgba([x as u8, y as u8, 0, 255]));
+        let tiles = connected_tiles(&tile, u32::MAX);
+        assert_eq!(tiles.len(), 16);
+        assert_eq!(tiles[0], tile);
+        assert_eq!(tiles[15].get_pixel(0, 0), tile.get_pixel(2, 1));
+        assert_eq!(tiles[15].get_pixel(3, 1), tile.get_pixel(1, 0));
+    }
+}
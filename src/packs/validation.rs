@@ -0,0 +1,244 @@
+use std::{collections::HashSet, fmt::Display};
+
+use super::utils::SpriteArray;
+use crate::{BlockFace, BlockState, SolidBlockDefinition, TextureIndex};
+
+/// One problem with a pack
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue {
+    /// Sprite name a block refers to that the atlas lacks
+    MissingSprite(String),
+    /// Sprite reaching past the atlas image
+    OutOfImage(String),
+    /// Two sprites sharing atlas pixels
+    Overlap(String, String),
+    /// Sprite no block shows
+    Unused(String),
+    /// Block face showing no texture of the pack
+    UndefinedFace {
+        block: String,
+        state: BlockState,
+        face: BlockFace,
+    },
+    /// Variant, connected tile or animation frame past the texture layers
+    MissingLayer { block: String, layer: u16 },
+}
+
+impl Issue {
+    /// Whether the pack still renders as intended despite this issue
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Issue::Unused(_))
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::MissingSprite(name) => write!(f, "no sprite called `{}`", name),
+            Issue::OutOfImage(name) => {
+                write!(f, "sprite `{}` lies outside the atlas image", name)
+            }
+            Issue::Overlap(first, second) => {
+                write!(f, "sprites `{}` and `{}` overlap", first, second)
+            }
+            Issue::Unused(name) => write!(f, "sprite `{}` is not used by any block", name),
+            Issue::UndefinedFace { block, state, face } => write!(
+                f,
+                "block {} has no texture for its {:?} face in state {}",
+                block, face, state
+            ),
+            Issue::MissingLayer { block, layer } => {
+                write!(
+                    f,
+                    "block {} refers to missing texture layer {}",
+                    block, layer
+                )
+            }
+        }
+    }
+}
+
+/// Every problem found in a pack, rather than a panic at the first
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(|issue| !issue.is_warning())
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues.iter().filter(|issue| issue.is_warning())
+    }
+
+    /// Whether the pack has no errors, warnings aside
+    pub fn is_ok(&self) -> bool {
+        self.errors().next().is_none()
+    }
+}
+
+/// One issue per line, errors first
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for issue in self.errors() {
+            writeln!(f, "error: {}", issue)?;
+        }
+        for issue in self.warnings() {
+            writeln!(f, "warning: {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check the definitions of `blocks`, by name, against a pack of `layers`
+/// texture layers
+///
+/// With an `atlas`, its sprites and image size, the sprites are the first
+/// layers and are checked too: they have to lie inside the image without
+/// overlapping and be shown by some block, and every name looked up with
+/// `SpriteArray`'s `Index` has to exist. Look up the names (build the
+/// definitions) before validating.
+pub fn validate<'a>(
+    atlas: Option<(&SpriteArray, (u32, u32))>,
+    layers: usize,
+    blocks: impl IntoIterator<Item = (String, &'a SolidBlockDefinition)>,
+) -> Report {
+    let mut issues = Vec::new();
+    let mut used = HashSet::new();
+    for (block, definition) in blocks {
+        let mut undefined = HashSet::new();
+        for state in 0..definition.state_count() {
+            let state = BlockState(state as u8);
+            for (face, &texture) in definition.faces(state) {
+                if texture.0 as usize >= layers {
+                    undefined.insert(texture);
+                    issues.push(Issue::UndefinedFace {
+                        block: block.clone(),
+                        state,
+                        face,
+                    });
+                }
+            }
+        }
+        for texture in definition.textures() {
+            used.insert(texture);
+            if texture.0 as usize >= layers && undefined.insert(texture) {
+                issues.push(Issue::MissingLayer {
+                    block: block.clone(),
+                    layer: texture.0,
+                });
+            }
+        }
+    }
+    if let Some((sprites, (width, height))) = atlas {
+        let mut missing = sprites.missing();
+        missing.sort();
+        missing.dedup();
+        issues.extend(missing.into_iter().map(Issue::MissingSprite));
+        let name = |index: usize| {
+            sprites
+                .name(TextureIndex(index as u16))
+                .map_or_else(|| format!("#{}", index), str::to_string)
+        };
+        let rects = sprites.sprites();
+        for (index, sprite) in rects.iter().enumerate() {
+            if !sprite.fits(width, height) {
+                issues.push(Issue::OutOfImage(name(index)));
+            }
+            let end = |start: u32, size: u32| u64::from(start) + u64::from(size);
+            for (other_index, other) in rects.iter().enumerate().skip(index + 1) {
+                if u64::from(sprite.x) < end(other.x, other.width)
+                    && u64::from(other.x) < end(sprite.x, sprite.width)
+                    && u64::from(sprite.y) < end(other.y, other.height)
+                    && u64::from(other.y) < end(sprite.y, sprite.height)
+                {
+                    issues.push(Issue::Overlap(name(index), name(other_index)));
+                }
+            }
+            if !used.contains(&TextureIndex(index as u16)) {
+                issues.push(Issue::Unused(name(index)));
+            }
+        }
+    }
+    Report { issues }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packs::{basic::BasicPack, utils::SpriteDefinition, Pack};
+
+    use super::*;
+
+    #[test]
+    fn basic_pack_is_valid() {
+        let report = BasicPack::validate();
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[test]
+    fn reports_every_issue() {
+        let sprites: SpriteArray = vec![
+            ("a.png", SpriteDefinition::from((0, 0, 16, 16))),
+            ("b.png", SpriteDefinition::from((8, 8, 16, 16))),
+            ("c.png", SpriteDefinition::from((24, 0, 16, 16))),
+        ]
+        .into_iter()
+        .collect();
+        let broken = SolidBlockDefinition::from(|face| match face {
+            BlockFace::Up => sprites["b.png"],
+            BlockFace::Down => sprites["nope.png"],
+            _ => sprites["a.png"],
+        })
+        .with_connected(sprites["a.png"], [TextureIndex(7); 16]);
+        let report = validate(
+            Some((&sprites, (32, 32))),
+            3,
+            vec![("broken".to_string(), &broken)],
+        );
+        let undefined = |face| Issue::UndefinedFace {
+            block: "broken".into(),
+            state: BlockState(0),
+            face,
+        };
+        assert_eq!(
+            report.issues,
+            vec![
+                undefined(BlockFace::Down),
+                Issue::MissingLayer {
+                    block: "broken".into(),
+                    layer: 7
+                },
+                Issue::MissingSprite("nope.png".into()),
+                Issue::Overlap("a.png".into(), "b.png".into()),
+                Issue::OutOfImage("c.png".into()),
+                Issue::Unused("c.png".into()),
+            ]
+        );
+        assert!(!report.is_ok());
+        assert_eq!(report.warnings().count(), 1);
+        assert!(report.to_string().starts_with("error: block broken has no"));
+    }
+
+    #[test]
+    fn handles_sprites_past_u32_max() {
+        let sprites: SpriteArray = vec![
+            ("a.png", SpriteDefinition::from((u32::MAX - 8, 0, 16, 16))),
+            ("b.png", SpriteDefinition::from((u32::MAX, 0, 16, 16))),
+        ]
+        .into_iter()
+        .collect();
+        let report = validate(Some((&sprites, (32, 32))), 0, vec![]);
+        assert_eq!(
+            report.issues,
+            vec![
+                Issue::OutOfImage("a.png".into()),
+                Issue::Overlap("a.png".into(), "b.png".into()),
+                Issue::Unused("a.png".into()),
+                Issue::OutOfImage("b.png".into()),
+                Issue::Unused("b.png".into()),
+            ]
+        );
+    }
+}
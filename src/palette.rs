@@ -0,0 +1,222 @@
+use std::{fmt::Display, ops::Index};
+
+use crate::{Block, BlockId, BlockSubPos, BlockSubPosIterator, Chunk, ChunkStorage};
+
+/// Chunk storing a small palette of distinct blocks plus bit-packed indices into it
+///
+/// Indices never straddle two words, so each `u64` holds `64 / bits` of them.
+/// A chunk holding a single kind of block needs no index data at all.
+#[derive(Debug, Clone)]
+pub struct PaletteChunk<Id: BlockId, const SIZE: usize, const WIDTH: usize> {
+    palette: Vec<Block<Id>>,
+    bits: usize,
+    data: Vec<u64>,
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> Default for PaletteChunk<Id, SIZE, WIDTH> {
+    fn default() -> Self {
+        Self {
+            palette: vec![Block::Empty],
+            bits: 0,
+            data: Vec::new(),
+        }
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> Display for PaletteChunk<Id, SIZE, WIDTH> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "PaletteChunk<{}, {}, {}>",
+            std::any::type_name::<Id>(),
+            SIZE,
+            WIDTH
+        )?;
+        for (pos, block) in self {
+            writeln!(f, "{}: {}", pos, block)?;
+        }
+        Ok(())
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> PaletteChunk<Id, SIZE, WIDTH> {
+    pub const HEIGHT: usize = SIZE / WIDTH / WIDTH;
+
+    fn per_word(bits: usize) -> usize {
+        64 / bits
+    }
+
+    fn index_at(&self, index: usize) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+        let per_word = Self::per_word(self.bits);
+        let word = self.data[index / per_word];
+        let shift = (index % per_word) * self.bits;
+        ((word >> shift) & ((1 << self.bits) - 1)) as usize
+    }
+
+    fn set_index_at(&mut self, index: usize, value: usize) {
+        let per_word = Self::per_word(self.bits);
+        let shift = (index % per_word) * self.bits;
+        let mask = ((1u64 << self.bits) - 1) << shift;
+        let word = &mut self.data[index / per_word];
+        *word = (*word & !mask) | ((value as u64) << shift);
+    }
+
+    /// Repack every index using `bits` bits each
+    fn resize(&mut self, bits: usize) {
+        let indices: Vec<usize> = (0..SIZE).map(|i| self.index_at(i)).collect();
+        self.bits = bits;
+        self.data = vec![0; SIZE.div_ceil(Self::per_word(bits))];
+        for (i, value) in indices.into_iter().enumerate() {
+            self.set_index_at(i, value);
+        }
+    }
+
+    /// Distinct blocks referenced by this chunk (including ones no longer in use)
+    pub fn palette(&self) -> &[Block<Id>] {
+        &self.palette
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> Index<BlockSubPos<SIZE, WIDTH>>
+    for PaletteChunk<Id, SIZE, WIDTH>
+{
+    type Output = Block<Id>;
+
+    fn index(&self, index: BlockSubPos<SIZE, WIDTH>) -> &Self::Output {
+        &self.palette[self.index_at(index.as_index())]
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> ChunkStorage<Id, SIZE, WIDTH>
+    for PaletteChunk<Id, SIZE, WIDTH>
+{
+    fn set(&mut self, pos: BlockSubPos<SIZE, WIDTH>, block: Block<Id>) {
+        let value = match self.palette.iter().position(|&entry| entry == block) {
+            Some(value) => value,
+            None => {
+                self.palette.push(block);
+                let needed = usize::BITS - (self.palette.len() - 1).leading_zeros();
+                if needed as usize > self.bits {
+                    self.resize(needed as usize);
+                }
+                self.palette.len() - 1
+            }
+        };
+        if self.bits != 0 {
+            self.set_index_at(pos.as_index(), value);
+        }
+    }
+
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.palette.capacity() * std::mem::size_of::<Block<Id>>()
+            + self.data.capacity() * std::mem::size_of::<u64>()
+    }
+}
+
+pub struct PaletteChunkIter<'chunk, Id: BlockId, const SIZE: usize, const WIDTH: usize> {
+    chunk: &'chunk PaletteChunk<Id, SIZE, WIDTH>,
+    positions: BlockSubPosIterator<SIZE, WIDTH>,
+}
+
+impl<'chunk, Id: BlockId, const SIZE: usize, const WIDTH: usize> Iterator
+    for PaletteChunkIter<'chunk, Id, SIZE, WIDTH>
+{
+    type Item = (BlockSubPos<SIZE, WIDTH>, &'chunk Block<Id>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunk;
+        self.positions.next().map(|pos| (pos, &chunk[pos]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.positions.size_hint()
+    }
+}
+
+impl<'chunk, Id: BlockId, const SIZE: usize, const WIDTH: usize> IntoIterator
+    for &'chunk PaletteChunk<Id, SIZE, WIDTH>
+{
+    type Item = (BlockSubPos<SIZE, WIDTH>, &'chunk Block<Id>);
+
+    type IntoIter = PaletteChunkIter<'chunk, Id, SIZE, WIDTH>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PaletteChunkIter {
+            chunk: self,
+            positions: Default::default(),
+        }
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> From<&Chunk<Id, SIZE, WIDTH>>
+    for PaletteChunk<Id, SIZE, WIDTH>
+{
+    fn from(chunk: &Chunk<Id, SIZE, WIDTH>) -> Self {
+        let mut ret = Self::default();
+        for (pos, &block) in chunk {
+            ret.set(pos, block);
+        }
+        ret
+    }
+}
+
+impl<Id: BlockId, const SIZE: usize, const WIDTH: usize> From<&PaletteChunk<Id, SIZE, WIDTH>>
+    for Chunk<Id, SIZE, WIDTH>
+{
+    fn from(chunk: &PaletteChunk<Id, SIZE, WIDTH>) -> Self {
+        let mut ret = Self::default();
+        for (pos, &block) in chunk {
+            ret[pos] = block;
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packs::basic::BasicId;
+
+    use super::*;
+
+    type TestChunk = PaletteChunk<BasicId, 4096, 16>;
+
+    #[test]
+    fn empty_chunk_has_no_index_data() {
+        let chunk = TestChunk::default();
+        assert_eq!(chunk.data.len(), 0);
+        assert!(chunk.iter_solid().next().is_none());
+    }
+
+    #[test]
+    fn grows_with_palette() {
+        let mut chunk = TestChunk::default();
+        let ids = [BasicId::Dirt, BasicId::Stone, BasicId::Sand, BasicId::Wood];
+        for (i, pos) in BlockSubPosIterator::<4096, 16>::default().enumerate() {
+            chunk.set(pos, Block::solid(ids[i % ids.len()]));
+        }
+        assert_eq!(chunk.palette().len(), 5);
+        assert_eq!(chunk.bits, 3);
+        for (i, (pos, &block)) in chunk.into_iter().enumerate() {
+            assert_eq!(block, Block::solid(ids[i % ids.len()]));
+            assert_eq!(chunk.get(pos), block);
+        }
+    }
+
+    #[test]
+    fn round_trips_dense_chunk() {
+        let mut dense = Chunk::<BasicId, 4096, 16>::default();
+        for (pos, block) in &mut dense {
+            let (x, y, z) = pos.into();
+            if (x + y * 3 + z * 7) % 5 == 0 {
+                *block = Block::solid(BasicId::GreyStone);
+            }
+        }
+        let palette = TestChunk::from(&dense);
+        assert!(palette.iter_solid().eq(dense.iter_solid()));
+        assert_eq!(Chunk::from(&palette).0[..], dense.0[..]);
+    }
+}
@@ -0,0 +1,461 @@
+use std::{collections::HashMap, convert::TryFrom, fmt::Display, path::Path};
+
+use crate::{packs::SimpleBlocks, BlockFace, BlockKind, SolidBlockDefinition, TextureIndex};
+use enum_map::{Enum, EnumMap};
+
+/// Numeric id of a block registered at runtime, see `Registry`
+///
+/// Ids are handed out in registration order and index an `EnumMap` like any
+/// enum id, which caps a registry at 256 blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RegistryId(pub u8);
+
+impl<T> Enum<T> for RegistryId {
+    type Array = <u8 as Enum<T>>::Array;
+
+    const POSSIBLE_VALUES: usize = <u8 as Enum<T>>::POSSIBLE_VALUES;
+
+    fn slice(array: &Self::Array) -> &[T] {
+        <u8 as Enum<T>>::slice(array)
+    }
+
+    fn slice_mut(array: &mut Self::Array) -> &mut [T] {
+        <u8 as Enum<T>>::slice_mut(array)
+    }
+
+    fn from_usize(value: usize) -> Self {
+        RegistryId(<u8 as Enum<T>>::from_usize(value))
+    }
+
+    fn to_usize(self) -> usize {
+        <u8 as Enum<T>>::to_usize(self.0)
+    }
+
+    fn from_function<F: FnMut(Self) -> T>(mut f: F) -> Self::Array {
+        <u8 as Enum<T>>::from_function(|id| f(RegistryId(id)))
+    }
+}
+
+/// Prints the number, `Registry::properties` has the name
+impl Display for RegistryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Role a generator may look a block up by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockTag {
+    /// Filler, see `SimpleBlocks::simple`
+    Simple,
+    /// Surface, see `SimpleBlocks::top`
+    Top,
+}
+
+/// What a registry knows about a block besides its `SolidBlockDefinition`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockProperties {
+    pub name: String,
+    pub tags: Vec<BlockTag>,
+}
+
+/// Why a block list could not be loaded
+#[derive(Debug)]
+pub enum RegistryError {
+    Io(std::io::Error),
+    /// Malformed entry on a (1-based) line
+    Parse {
+        line: usize,
+        message: String,
+    },
+    /// A second block with an already registered name
+    Duplicate(String),
+    /// More blocks than `RegistryId` can address
+    Full,
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::Io(err) => write!(f, "cannot read block list: {}", err),
+            RegistryError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            RegistryError::Duplicate(name) => write!(f, "`{}` is registered twice", name),
+            RegistryError::Full => write!(
+                f,
+                "too many blocks, at most {} fit in a registry",
+                Registry::CAPACITY
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+impl From<std::io::Error> for RegistryError {
+    fn from(err: std::io::Error) -> Self {
+        RegistryError::Io(err)
+    }
+}
+
+/// Named blocks with numeric ids, built at runtime instead of from an enum
+///
+/// A `Registry` is used like a `Pack`: `definitions` indexes by `RegistryId`
+/// just as `Pack::get_map` does by enum id, so `World`, the meshers and the
+/// renderers work with `RegistryId` unchanged. Block lists are plain text, one
+/// block per line:
+///
+/// ```text
+/// # name      kind         textures                          tags
+/// dirt        opaque       all=dirt                          simple
+/// dirt_grass  opaque       up=grass_top down=dirt side=dirt_grass top
+/// glass       transparent  all=glass
+/// ```
+///
+/// Kinds are `opaque`, `transparent`, `cross` and `liquid`. Textures are
+/// `face=name` pairs naming sprites of the pack, applied as `all`, then `side`
+/// (north, south, east and west), then single faces. Bare words are tags, see
+/// `BlockTag`. Blank lines and `#` comments are skipped.
+#[derive(Debug)]
+pub struct Registry {
+    blocks: Vec<BlockProperties>,
+    names: HashMap<String, RegistryId>,
+    definitions: Box<EnumMap<RegistryId, SolidBlockDefinition>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            blocks: Vec::new(),
+            names: HashMap::new(),
+            definitions: Box::new(EnumMap::from(|_| {
+                SolidBlockDefinition::new_simple_block(TextureIndex::default())
+            })),
+        }
+    }
+}
+
+impl Registry {
+    /// Most blocks a registry holds
+    pub const CAPACITY: usize = <RegistryId as Enum<()>>::POSSIBLE_VALUES;
+
+    /// Parse a block list, resolving texture names with `sprite`
+    pub fn parse(
+        source: &str,
+        sprite: impl Fn(&str) -> Option<TextureIndex>,
+    ) -> Result<Self, RegistryError> {
+        let mut registry = Self::default();
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| RegistryError::Parse {
+                line: index + 1,
+                message,
+            };
+            let mut words = line.split_whitespace();
+            let name = words.next().unwrap_or_default();
+            let kind = match words.next() {
+                Some("opaque") => BlockKind::Opaque,
+                Some("transparent") => BlockKind::Transparent,
+                Some("cross") => BlockKind::Cross,
+                Some("liquid") => BlockKind::Liquid,
+                Some(other) => return Err(error(format!("unknown kind `{}`", other))),
+                None => return Err(error(format!("`{}` has no kind", name))),
+            };
+            let mut textures = Vec::new();
+            let mut tags = Vec::new();
+            for word in words {
+                match word.split_once('=') {
+                    Some((faces, texture)) => {
+                        let texture = sprite(texture)
+                            .ok_or_else(|| error(format!("unknown texture `{}`", texture)))?;
+                        textures.push((faces, texture));
+                    }
+                    None => tags.push(match word {
+                        "simple" => BlockTag::Simple,
+                        "top" => BlockTag::Top,
+                        _ => return Err(error(format!("unknown tag `{}`", word))),
+                    }),
+                }
+            }
+            let faces = face_textures(&textures).map_err(error)?;
+            registry
+                .register(
+                    BlockProperties {
+                        name: name.into(),
+                        tags,
+                    },
+                    SolidBlockDefinition::from(faces).with_kind(kind),
+                )
+                .map_err(|err| match err {
+                    RegistryError::Duplicate(_) => error(err.to_string()),
+                    err => err,
+                })?;
+        }
+        Ok(registry)
+    }
+
+    /// Read and parse the block list at `path`, see `parse`
+    pub fn load(
+        path: impl AsRef<Path>,
+        sprite: impl Fn(&str) -> Option<TextureIndex>,
+    ) -> Result<Self, RegistryError> {
+        Self::parse(&std::fs::read_to_string(path)?, sprite)
+    }
+
+    /// Add a block under the next free id
+    pub fn register(
+        &mut self,
+        properties: BlockProperties,
+        definition: SolidBlockDefinition,
+    ) -> Result<RegistryId, RegistryError> {
+        let id = RegistryId(u8::try_from(self.blocks.len()).map_err(|_| RegistryError::Full)?);
+        if self.names.contains_key(&properties.name) {
+            return Err(RegistryError::Duplicate(properties.name));
+        }
+        self.names.insert(properties.name.clone(), id);
+        self.blocks.push(properties);
+        self.definitions[id] = definition;
+        Ok(id)
+    }
+
+    /// Number of registered blocks
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Id of the block called `name`
+    pub fn id(&self, name: &str) -> Option<RegistryId> {
+        self.names.get(name).copied()
+    }
+
+    pub fn properties(&self, id: RegistryId) -> Option<&BlockProperties> {
+        self.blocks.get(id.0 as usize)
+    }
+
+    /// Definitions of every id, unregistered ids map to a placeholder
+    pub fn definitions(&self) -> &EnumMap<RegistryId, SolidBlockDefinition> {
+        &self.definitions
+    }
+
+    /// Registered ids, in registration order
+    pub fn ids(&self) -> impl Iterator<Item = RegistryId> {
+        (0..self.blocks.len()).map(|id| RegistryId(id as u8))
+    }
+
+    /// First block carrying `tag`
+    pub fn tagged(&self, tag: BlockTag) -> Option<RegistryId> {
+        self.ids()
+            .find(|&id| self.blocks[id.0 as usize].tags.contains(&tag))
+    }
+
+    /// The `simple` and `top` tagged blocks, the first block standing in for
+    /// an untagged one, with the opaque blocks to pick random ones from;
+    /// `None` for an empty registry
+    pub fn simple_blocks(&self) -> Option<SimpleBlocks<RegistryId>> {
+        let first = self.ids().next()?;
+        let random = self
+            .ids()
+            .filter(|&id| self.definitions[id].kind() == BlockKind::Opaque)
+            .collect();
+        Some(SimpleBlocks::new(
+            self.tagged(BlockTag::Simple).unwrap_or(first),
+            self.tagged(BlockTag::Top).unwrap_or(first),
+            random,
+        ))
+    }
+}
+
+/// Faces a texture assignment in a block list covers
+fn face_targets(name: &str) -> Option<&'static [BlockFace]> {
+    Some(match name {
+        "all" => &[
+            BlockFace::North,
+            BlockFace::South,
+            BlockFace::East,
+            BlockFace::West,
+            BlockFace::Up,
+            BlockFace::Down,
+        ],
+        "side" => &[
+            BlockFace::North,
+            BlockFace::South,
+            BlockFace::East,
+            BlockFace::West,
+        ],
+        "north" => &[BlockFace::North],
+        "south" => &[BlockFace::South],
+        "east" => &[BlockFace::East],
+        "west" => &[BlockFace::West],
+        "up" => &[BlockFace::Up],
+        "down" => &[BlockFace::Down],
+        _ => return None,
+    })
+}
+
+/// Resolve face assignments, narrower ones overriding broader ones
+fn face_textures(
+    textures: &[(&str, TextureIndex)],
+) -> Result<EnumMap<BlockFace, TextureIndex>, String> {
+    let mut assignments = textures
+        .iter()
+        .map(|&(name, texture)| {
+            face_targets(name)
+                .map(|targets| (targets, texture))
+                .ok_or_else(|| format!("unknown face `{}`", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    assignments.sort_by_key(|(targets, _)| std::cmp::Reverse(targets.len()));
+    let mut faces: EnumMap<BlockFace, Option<TextureIndex>> = EnumMap::default();
+    for (targets, texture) in assignments {
+        for &face in targets {
+            faces[face] = Some(texture);
+        }
+    }
+    if let Some((face, _)) = faces.iter().find(|(_, texture)| texture.is_none()) {
+        return Err(format!("no texture for the {:?} face", face));
+    }
+    Ok(EnumMap::from(|face| faces[face].unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generator::{
+            flat::Flat,
+            random::{RandomFill, RandomGenerator},
+            WorldGenerator,
+        },
+        mesher::{culling::Culling, Mesher},
+        packs::basic::BasicPack,
+        ChunkCoord, ChunkStorage, World,
+    };
+
+    use super::*;
+
+    const BLOCKS: &str = "
+        # name      kind         textures
+        dirt        opaque       all=dirt                  simple
+        dirt_grass  opaque       all=dirt side=dirt_grass up=grass_top top
+        glass       transparent  all=glass
+    ";
+
+    #[test]
+    fn parses_block_list() {
+        let registry = Registry::parse(BLOCKS, BasicPack::sprite).unwrap();
+        assert_eq!(registry.len(), 3);
+        let grass = registry.id("dirt_grass").unwrap();
+        assert_eq!(grass, RegistryId(1));
+        assert_eq!(registry.tagged(BlockTag::Top), Some(grass));
+        let definition = &registry.definitions()[grass];
+        assert_eq!(
+            definition[BlockFace::Up],
+            BasicPack::sprite("grass_top").unwrap()
+        );
+        assert_eq!(
+            definition[BlockFace::Down],
+            BasicPack::sprite("dirt").unwrap()
+        );
+        assert_eq!(
+            definition[BlockFace::East],
+            BasicPack::sprite("dirt_grass").unwrap()
+        );
+        assert_eq!(
+            registry.definitions()[RegistryId(2)].kind(),
+            BlockKind::Transparent
+        );
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        for (source, line) in &[
+            ("dirt opaque all=nope", 1),
+            ("dirt opaque all=dirt\n\nstone solid all=stone", 3),
+            ("dirt opaque up=dirt", 1),
+            ("dirt opaque all=dirt\ndirt opaque all=dirt", 2),
+        ] {
+            match Registry::parse(source, BasicPack::sprite) {
+                Err(RegistryError::Parse { line: at, .. }) => assert_eq!(at, *line, "{}", source),
+                other => panic!("{} parsed as {:?}", source, other),
+            }
+        }
+    }
+
+    #[test]
+    fn loads_bundled_block_list() {
+        let registry = Registry::load("assets/blocks.txt", BasicPack::sprite).unwrap();
+        assert_eq!(registry.tagged(BlockTag::Simple), registry.id("dirt"));
+        assert_eq!(registry.tagged(BlockTag::Top), registry.id("dirt_grass"));
+    }
+
+    #[test]
+    fn picks_simple_blocks() {
+        assert_eq!(Registry::default().simple_blocks(), None);
+        let registry = Registry::parse(BLOCKS, BasicPack::sprite).unwrap();
+        let blocks = registry.simple_blocks().unwrap();
+        assert_eq!(blocks.simple, registry.id("dirt").unwrap());
+        assert_eq!(blocks.top, registry.id("dirt_grass").unwrap());
+        assert_eq!(blocks.random_blocks(), &[RegistryId(0), RegistryId(1)]);
+        let mut world = World::<RegistryId, 4, 2, 4096, 16>::create();
+        RandomGenerator::with_blocks(RandomFill::Fill, 3, blocks).generate(world.as_mut());
+        for (_, chunk) in world.as_ref() {
+            for (_, id) in chunk.iter_solid() {
+                assert_ne!(id, registry.id("glass").unwrap());
+            }
+        }
+        // Without opaque blocks random fills fall back to the simple block
+        let glass = Registry::parse("glass transparent all=glass", BasicPack::sprite).unwrap();
+        assert_eq!(glass.tagged(BlockTag::Simple), None);
+        let blocks = glass.simple_blocks().unwrap();
+        assert_eq!((blocks.simple, blocks.top), (RegistryId(0), RegistryId(0)));
+        assert_eq!(blocks.random_blocks(), &[RegistryId(0)]);
+    }
+
+    #[test]
+    fn refuses_more_than_capacity() {
+        let mut registry = Registry::default();
+        let block = |index: usize| {
+            let properties = BlockProperties {
+                name: format!("block{}", index),
+                tags: Vec::new(),
+            };
+            (
+                properties,
+                SolidBlockDefinition::new_simple_block(TextureIndex(0)),
+            )
+        };
+        for index in 0..Registry::CAPACITY {
+            let (properties, definition) = block(index);
+            assert_eq!(
+                registry.register(properties, definition).unwrap(),
+                RegistryId(index as u8)
+            );
+        }
+        let (properties, definition) = block(Registry::CAPACITY);
+        assert!(matches!(
+            registry.register(properties, definition),
+            Err(RegistryError::Full)
+        ));
+        assert_eq!(registry.len(), Registry::CAPACITY);
+    }
+
+    #[test]
+    fn meshes_registry_world() {
+        let registry = Registry::parse(BLOCKS, BasicPack::sprite).unwrap();
+        let dirt = registry.id("dirt").unwrap();
+        let grass = registry.id("dirt_grass").unwrap();
+        let mut world = World::<RegistryId, 4, 2, 4096, 16>::create();
+        Flat::new(vec![Some(dirt), Some(grass)]).generate(world.as_mut());
+        let mesh = Culling.mesh(
+            world.as_ref(),
+            registry.definitions(),
+            ChunkCoord::new(0, 0),
+        );
+        assert!(!mesh.is_empty());
+    }
+}
@@ -1,104 +1,341 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use enum_map::EnumMap;
 use glium::{
     backend::Facade,
     glutin::{self, event::ElementState},
+    texture::{MipmapsOption, RawImage2d, UnsignedTexture1d},
     Frame,
 };
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::{
+    animation_table,
     camera::{model_camera::ModelCamera, Camera, CameraCreation, CameraInput},
-    generator::WorldGenerator,
-    packs::{basic::*, Pack, SimpleBlockId},
-    BlockId, SolidBlockDefinition, World,
+    generator::{
+        flat::Flat,
+        heightmap::Heightmap,
+        random::{RandomFill, RandomGenerator},
+        ChunkGenerator,
+    },
+    packs::{
+        array::{check_uniform, ArrayOptions, SpriteFit, TextureArray},
+        basic::*,
+        compose::{ComposedPack, PackSource},
+        loader::LoadedPack,
+        Pack, SimpleBlocks,
+    },
+    world_hash, Block, BlockId, ChunkCoord, ClimateSource, DynamicWorld, Registry, RegistryId,
+    SolidBlockDefinition, WorldPosition,
 };
 
-pub struct WorldInfo<
-    Id: BlockId,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
-> {
+/// Blocks in a chunk drawn by the renderers, 32 high
+pub const CHUNK_SIZE: usize = 8192;
+/// Blocks along each horizontal side of a chunk drawn by the renderers
+pub const CHUNK_WIDTH: usize = 16;
+
+pub struct WorldInfo<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize> {
     pub camera: Box<dyn Camera>,
-    pub world: Box<World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>,
+    pub world: DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH>,
     pub definitions: &'static EnumMap<Id, SolidBlockDefinition>,
     pub texture: glium::texture::srgb_texture2d_array::SrgbTexture2dArray,
+    /// `animation_table` of `definitions`, for the `animations` shader uniform
+    pub animations: UnsignedTexture1d,
+    /// Time since the renderer started, for the `time` shader uniform
+    pub time: Duration,
+    /// Climate of the generated world, to tint meshes with (see `Mesher::mesh_in`)
+    pub climate: Box<dyn ClimateSource>,
 }
 
-fn mock_gen_world<
-    F: Facade,
-    P: Pack,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
->(
-    facade: &F,
-) -> Result<WorldInfo<P::Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>
-where
-    P::Id: SimpleBlockId,
+impl<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>
 {
-    let texture = glium::texture::srgb_texture2d_array::SrgbTexture2dArray::new(
-        facade,
-        BasicPack::get_textures(),
-    )?;
-    let generator = crate::generator::random::RandomGenerator::Odd;
-    let mut world = World::create();
-    generator.generate(world.as_mut());
-    let width = WIDTH * CHUNK_WIDTH;
+    /// `time` in milliseconds, wrapping around after about 49 days
+    pub fn time_millis(&self) -> u32 {
+        self.time.as_millis() as u32
+    }
+}
+
+/// World seed from the `VOXEL_SEED` environment variable, default 0, so runs
+/// compare on the same world
+fn select_seed() -> Result<u64> {
+    match std::env::var("VOXEL_SEED") {
+        Ok(seed) => Ok(seed.parse()?),
+        Err(_) => Ok(0),
+    }
+}
+
+/// World size in chunks from the `VOXEL_WIDTH` (along x) and `VOXEL_LENGTH`
+/// (along z) environment variables, default 4 by 4
+fn select_world_size() -> Result<(usize, usize)> {
+    let chunks = |name| -> Result<usize> {
+        match std::env::var(name) {
+            Ok(chunks) => Ok(chunks.parse()?),
+            Err(_) => Ok(4),
+        }
+    };
+    let (width, length) = (chunks("VOXEL_WIDTH")?, chunks("VOXEL_LENGTH")?);
+    if width == 0 || length == 0 {
+        anyhow::bail!("empty world of {}x{} chunks", width, length);
+    }
+    Ok((width, length))
+}
+
+/// Pick the world generator from the `VOXEL_GENERATOR` environment variable
+/// (`odd`, `fill`, `flat` or `terrain`), defaulting to `odd`, building from
+/// `blocks`
+fn select_generator<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
+    seed: u64,
+    blocks: &SimpleBlocks<Id>,
+) -> Result<Box<dyn ChunkGenerator<Id, CHUNK_SIZE, CHUNK_WIDTH>>> {
+    let name = std::env::var("VOXEL_GENERATOR").unwrap_or_else(|_| "odd".into());
+    Ok(match name.as_str() {
+        "odd" => Box::new(RandomGenerator::with_blocks(
+            RandomFill::Odd,
+            seed,
+            blocks.clone(),
+        )),
+        "fill" => Box::new(RandomGenerator::with_blocks(
+            RandomFill::Fill,
+            seed,
+            blocks.clone(),
+        )),
+        "flat" => Box::new(Flat::from_blocks(
+            blocks,
+            CHUNK_SIZE / CHUNK_WIDTH / CHUNK_WIDTH / 2,
+        )),
+        "terrain" => Box::new(Heightmap::from_blocks(
+            seed,
+            (CHUNK_SIZE / CHUNK_WIDTH / CHUNK_WIDTH / 2) as u16,
+            blocks,
+        )),
+        _ => anyhow::bail!("unknown generator: {}", name),
+    })
+}
+
+/// Texture array shape from the `VOXEL_TEXTURE_SIZE` (layer side in pixels,
+/// default the largest texture), `VOXEL_TEXTURE_FIT` (`resample` or `pad`) and
+/// `VOXEL_MIPMAPS` (`gpu` or `cpu`) environment variables
+fn select_texture_options() -> Result<ArrayOptions> {
+    let size = match std::env::var("VOXEL_TEXTURE_SIZE") {
+        Ok(size) => Some(size.parse()?),
+        Err(_) => None,
+    };
+    let fit = match std::env::var("VOXEL_TEXTURE_FIT").as_deref() {
+        Ok("resample") | Err(_) => SpriteFit::Resample,
+        Ok("pad") => SpriteFit::Pad,
+        Ok(name) => anyhow::bail!("unknown texture fit: {}", name),
+    };
+    let mipmaps = match std::env::var("VOXEL_MIPMAPS").as_deref() {
+        Ok("gpu") | Err(_) => false,
+        Ok("cpu") => true,
+        Ok(name) => anyhow::bail!("unknown mipmap source: {}", name),
+    };
+    Ok(ArrayOptions { size, fit, mipmaps })
+}
+
+fn mock_gen_world<F: Facade, Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
+    facade: &F,
+    definitions: &'static EnumMap<Id, SolidBlockDefinition>,
+    textures: Vec<RawImage2d<'static, u8>>,
+    seed: u64,
+    blocks: &SimpleBlocks<Id>,
+) -> Result<WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>> {
+    if let Err(mixed) = check_uniform(&textures) {
+        log::warn!("mixed texture sizes, {}", mixed);
+    }
+    let options = select_texture_options()?;
+    let array = TextureArray::new(textures, &options);
+    log::info!(
+        "texture array {} layers of {}px, {} levels",
+        array.layer_count(),
+        array.size(),
+        array.level_count()
+    );
+    let texture = array.upload(facade)?;
+    let table = animation_table(
+        definitions
+            .values()
+            .flat_map(SolidBlockDefinition::animations),
+        array.layer_count(),
+    );
+    let animations = UnsignedTexture1d::with_mipmaps(facade, table, MipmapsOption::NoMipmap)?;
+    let generator = select_generator::<Id, CHUNK_SIZE, CHUNK_WIDTH>(seed, blocks)?;
+    let (width, length) = select_world_size()?;
+    let mut world = DynamicWorld::new(width, length);
+    world.generate(generator.as_ref());
+    log::info!(
+        "{}x{} chunks, seed {} world hash {:016x}",
+        width,
+        length,
+        seed,
+        world_hash(&world)
+    );
+    let width = width * CHUNK_WIDTH;
     let height = CHUNK_SIZE / CHUNK_WIDTH / CHUNK_WIDTH;
-    let length = SIZE / WIDTH * CHUNK_WIDTH;
+    let length = length * CHUNK_WIDTH;
     Ok(WorldInfo {
         camera: Box::new(ModelCamera::new(width, height, length)),
         world,
-        definitions: P::get_map(),
+        definitions,
         texture,
+        animations,
+        time: Duration::ZERO,
+        climate: generator,
     })
 }
 
-pub trait Renderer<
-    Id: BlockId,
-    const SIZE: usize,
-    const WIDTH: usize,
-    const CHUNK_SIZE: usize,
-    const CHUNK_WIDTH: usize,
->
-{
-    fn prepare(&mut self, info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>);
-    fn render(&self, frame: Frame, info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>);
+pub trait Renderer<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize> {
+    fn prepare(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>);
+
+    /// Re-prepare only the chunks in `dirty`, as returned by
+    /// `DynamicWorld::take_dirty`; renderers without per-chunk state fall back to
+    /// a full `prepare`
+    fn update(&mut self, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>, _dirty: &[ChunkCoord]) {
+        self.prepare(info);
+    }
+
+    fn render(&self, frame: Frame, info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>);
 }
 
 pub trait RendererProvider {
-    fn get_renderer<
-        F: Facade,
-        Id: BlockId,
-        const SIZE: usize,
-        const WIDTH: usize,
-        const CHUNK_SIZE: usize,
-        const CHUNK_WIDTH: usize,
-    >(
+    fn get_renderer<F: Facade, Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
         facade: &F,
-        info: &WorldInfo<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>,
-    ) -> Result<Box<dyn Renderer<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>>;
+        info: &WorldInfo<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    ) -> Result<Box<dyn Renderer<Id, CHUNK_SIZE, CHUNK_WIDTH>>>;
+}
+
+/// Number of random block edits applied per frame, from the `VOXEL_EDITS`
+/// environment variable (default 0, a static scene)
+fn select_edits() -> Result<usize> {
+    match std::env::var("VOXEL_EDITS") {
+        Ok(edits) => Ok(edits.parse()?),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Replace `count` blocks picked with `rng`, emptying each or filling it with
+/// one of `blocks` with equal chance
+pub fn random_edits<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
+    world: &mut DynamicWorld<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    count: usize,
+    blocks: &SimpleBlocks<Id>,
+    rng: &mut impl Rng,
+) {
+    for _ in 0..count {
+        let pos = WorldPosition::new(
+            rng.gen_range(0..(world.width() * CHUNK_WIDTH) as i32),
+            rng.gen_range(0..(CHUNK_SIZE / CHUNK_WIDTH / CHUNK_WIDTH) as u16),
+            rng.gen_range(0..(world.length() * CHUNK_WIDTH) as i32),
+        );
+        let block = if rng.gen() {
+            Block::solid(blocks.random(rng))
+        } else {
+            Block::Empty
+        };
+        world.set(pos, block).unwrap();
+    }
+}
+
+/// Overlay packs named by the `VOXEL_OVERLAYS` environment variable, a comma
+/// separated list of `atlas.xml:blocks.txt` pairs in increasing priority
+fn select_overlays() -> Result<Vec<LoadedPack>> {
+    let overlays = match std::env::var("VOXEL_OVERLAYS") {
+        Ok(overlays) => overlays,
+        Err(_) => return Ok(Vec::new()),
+    };
+    overlays
+        .split(',')
+        .map(|overlay| {
+            let (atlas, blocks) = overlay
+                .rsplit_once(':')
+                .ok_or_else(|| anyhow::anyhow!("overlay `{}` is not atlas:blocks", overlay))?;
+            let pack = LoadedPack::load(atlas, blocks)?;
+            log::info!("loaded overlay {}", atlas);
+            Ok(pack)
+        })
+        .collect()
 }
 
+/// Run with the blocks of the `BasicPack`, or with the block list named by the
+/// `VOXEL_BLOCKS` environment variable (see `Registry`); the list uses the
+/// basic tiles unless `VOXEL_PACK` names a TexturePacker atlas to load instead.
+/// Overlays (see `select_overlays`) are composed over either, see
+/// `ComposedPack`. The world is `select_world_size` chunks of `CHUNK_SIZE`
+/// blocks.
 pub fn run_renderer<P: RendererProvider>() -> Result<()> {
+    let overlays = select_overlays()?;
+    let base = match std::env::var("VOXEL_BLOCKS") {
+        Err(_) if overlays.is_empty() => {
+            return run_world::<P, BasicId>(
+                BasicPack::get_map(),
+                BasicPack::get_textures(),
+                SimpleBlocks::of(),
+            )
+        }
+        Err(_) => PackSource::of::<BasicPack>(),
+        Ok(blocks) => {
+            let source = match std::env::var("VOXEL_PACK") {
+                Ok(atlas) => {
+                    let pack = LoadedPack::load(&atlas, &blocks)?;
+                    log::info!("loaded pack {}", atlas);
+                    PackSource::from(&pack)
+                }
+                Err(_) => PackSource::new(
+                    BasicPack::get_textures(),
+                    BasicPack::sprite_names(),
+                    &Registry::load(&blocks, BasicPack::sprite)?,
+                ),
+            };
+            log::info!("loaded blocks from {}", blocks);
+            source
+        }
+    };
+    let pack =
+        ComposedPack::compose(std::iter::once(base).chain(overlays.iter().map(PackSource::from)))?;
+    let textures = pack.textures();
+    log::info!("composed {} blocks", pack.registry().len());
+    let blocks = pack
+        .registry()
+        .simple_blocks()
+        .ok_or_else(|| anyhow::anyhow!("no blocks to build a world from"))?;
+    // The renderer runs until the process exits, which is as long as the
+    // definitions need to live
+    let registry: &'static Registry = Box::leak(Box::new(pack.into_registry()));
+    run_world::<P, RegistryId>(registry.definitions(), textures, blocks)
+}
+
+/// Generate a world of `blocks` and render it until the window closes
+fn run_world<P: RendererProvider, Id: BlockId>(
+    definitions: &'static EnumMap<Id, SolidBlockDefinition>,
+    textures: Vec<RawImage2d<'static, u8>>,
+    blocks: SimpleBlocks<Id>,
+) -> Result<()> {
     let event_loop = glutin::event_loop::EventLoop::new();
     let wb = glutin::window::WindowBuilder::new();
     let cb = glutin::ContextBuilder::new().with_multisampling(4);
     let display = glium::Display::new(wb, cb, &event_loop)?;
 
     let start = Instant::now();
-    let mut world = mock_gen_world::<glium::Display, BasicPack, 16, 4, 8192, 16>(&display)?;
+    let edits = select_edits()?;
+    let seed = select_seed()?;
+    // Edits follow the seed too, after the world generation
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut world = mock_gen_world::<_, Id, CHUNK_SIZE, CHUNK_WIDTH>(
+        &display,
+        definitions,
+        textures,
+        seed,
+        &blocks,
+    )?;
     log::info!("start {:?}", start.elapsed());
     let world_created = Instant::now();
     let mut renderer = P::get_renderer(&display, &world)?;
     log::info!("renderer {:?}", world_created.elapsed());
     let renderer_created = Instant::now();
     renderer.prepare(&world);
+    world.world.take_dirty();
     log::info!("renderer prepare {:?}", renderer_created.elapsed());
     // let renderer_prepared = Instant::now();
 
@@ -154,7 +391,14 @@ pub fn run_renderer<P: RendererProvider>() -> Result<()> {
             _ => return,
         }
 
-        // renderer.prepare(&world);
+        random_edits(&mut world.world, edits, &blocks, &mut rng);
+        let dirty = world.world.take_dirty();
+        if !dirty.is_empty() {
+            let update_start = Instant::now();
+            renderer.update(&world, &dirty);
+            log::debug!("update {} chunks {:?}", dirty.len(), update_start.elapsed());
+        }
+        world.time = start.elapsed();
         renderer.render(display.draw(), &world);
     });
 }
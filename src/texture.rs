@@ -1,8 +1,10 @@
-use std::ops::Index;
+use std::{ops::Index, time::Duration};
 
 use enum_map::{enum_map, Enum, EnumMap};
 use strum_macros::EnumIter;
 
+use crate::{BlockKind, BlockState, StateKind, Tint, WorldPosition};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Enum, EnumIter)]
 pub enum BlockFace {
     North,
@@ -13,41 +15,300 @@ pub enum BlockFace {
     Down,
 }
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+impl BlockFace {
+    /// Unit step from a block towards the neighbour covering this face
+    pub fn offset(self) -> (i32, i32, i32) {
+        match self {
+            BlockFace::North => (0, 0, -1),
+            BlockFace::South => (0, 0, 1),
+            BlockFace::East => (1, 0, 0),
+            BlockFace::West => (-1, 0, 0),
+            BlockFace::Up => (0, 1, 0),
+            BlockFace::Down => (0, -1, 0),
+        }
+    }
+
+    /// Sides of this face along its texture axes, as `[u-, u+, v-, v+]` with
+    /// `u` and `v` running as in `Face::corners`
+    pub fn plane_sides(self) -> [BlockFace; 4] {
+        use BlockFace::*;
+        match self {
+            North => [East, West, Up, Down],
+            South => [West, East, Up, Down],
+            East => [South, North, Up, Down],
+            West => [North, South, Up, Down],
+            Up => [West, East, North, South],
+            Down => [West, East, South, North],
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct TextureIndex(pub u16);
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TextureInfo {
     index: u16,
     order: u8,
+    repeat: (u8, u8),
 }
 
 impl TextureInfo {
-    fn new(index: u16, order: u8) -> Self {
-        TextureInfo { index, order }
+    /// Largest number of texture repeats along one axis of a quad
+    pub const MAX_REPEAT: u8 = 0x7F;
+
+    fn new(index: u16, order: u8, repeat: (u8, u8)) -> Self {
+        TextureInfo {
+            index,
+            order,
+            repeat,
+        }
     }
 }
 
 impl TextureIndex {
+    /// Stands in for a sprite a pack could not find, see `packs::validation`
+    pub const MISSING: Self = TextureIndex(u16::MAX);
+
     pub fn into_arr(&self) -> [TextureInfo; 4] {
-        [
-            TextureInfo::new(self.0, 0b00),
-            TextureInfo::new(self.0, 0b01),
-            TextureInfo::new(self.0, 0b11),
-            TextureInfo::new(self.0, 0b10),
-        ]
+        self.into_arr_repeat(1, 1)
+    }
+
+    /// Corners of a quad which tiles the texture `u` times horizontally and `v` times vertically
+    pub fn into_arr_repeat(&self, u: u8, v: u8) -> [TextureInfo; 4] {
+        self.into_arr_transformed(u, v, TextureTransform::IDENTITY)
+    }
+
+    /// Like `into_arr_repeat`, with every tile turned by `transform`
+    ///
+    /// The transform only permutes the `order` of the corners, swapping the
+    /// repeats when the texture axes end up along the other quad axes, so
+    /// shaders decode transformed corners like any other.
+    pub fn into_arr_transformed(
+        &self,
+        u: u8,
+        v: u8,
+        transform: TextureTransform,
+    ) -> [TextureInfo; 4] {
+        assert!(u <= TextureInfo::MAX_REPEAT && v <= TextureInfo::MAX_REPEAT);
+        let repeat = if transform.rotation % 2 == 1 {
+            (v, u)
+        } else {
+            (u, v)
+        };
+        [0b00, 0b01, 0b11, 0b10]
+            .map(|order| TextureInfo::new(self.0, transform.apply(order), repeat))
+    }
+}
+
+/// Quarter turns and mirroring of the texture on a face
+///
+/// The mirror (along u) applies first, then the turns. Corner `order` bits are
+/// turned as `(u, v) -> (1 - v, u)`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureTransform {
+    rotation: u8,
+    flip: bool,
+}
+
+impl TextureTransform {
+    pub const IDENTITY: Self = Self {
+        rotation: 0,
+        flip: false,
+    };
+
+    pub fn new(quarter_turns: u8, flip: bool) -> Self {
+        Self {
+            rotation: quarter_turns % 4,
+            flip,
+        }
+    }
+
+    pub fn rotation(self) -> u8 {
+        self.rotation
+    }
+
+    pub fn is_flipped(self) -> bool {
+        self.flip
+    }
+
+    /// Rotation in the low two bits and the mirror in the third, for renderers
+    /// building texture coordinates themselves
+    pub fn bits(self) -> u8 {
+        self.rotation | (self.flip as u8) << 2
+    }
+
+    /// `self` followed by `next`
+    pub fn then(self, next: Self) -> Self {
+        let rotation = if next.flip {
+            next.rotation + 4 - self.rotation
+        } else {
+            next.rotation + self.rotation
+        };
+        Self::new(rotation, self.flip != next.flip)
+    }
+
+    /// Texture corner (`u << 1 | v`) shown at the quad corner `order` once transformed
+    fn apply(self, order: u8) -> u8 {
+        let (mut u, mut v) = (order >> 1 & 1, order & 1);
+        if self.flip {
+            u = 1 - u;
+        }
+        for _ in 0..self.rotation {
+            (u, v) = (1 - v, u);
+        }
+        u << 1 | v
     }
 }
 
-impl Into<u32> for TextureInfo {
-    fn into(self) -> u32 {
-        ((self.index as u32) << 16) + self.order as u32
+// Layout: index (16 bits) | v repeat (7 bits) | u repeat (7 bits) | order (2 bits)
+impl From<TextureInfo> for u32 {
+    fn from(info: TextureInfo) -> Self {
+        let (u, v) = info.repeat;
+        ((info.index as u32) << 16) + ((v as u32) << 9) + ((u as u32) << 2) + info.order as u32
     }
 }
 
-// Only solid block
-#[derive(Debug, Clone, Copy)]
-pub struct SolidBlockDefinition(pub EnumMap<BlockFace, TextureIndex>);
+/// Textures shown one after the other in place of the first one
+///
+/// Meshes only ever refer to the first frame; renderers pick the current frame
+/// on the GPU from `animation_table` and a time uniform, so animating needs no
+/// remeshing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Animation {
+    pub frames: Vec<TextureIndex>,
+    pub frame_time: Duration,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<TextureIndex>, frame_time: Duration) -> Self {
+        assert!(!frames.is_empty(), "an animation needs at least one frame");
+        assert!(
+            frame_time.as_millis() > 0,
+            "frames need to last at least 1ms"
+        );
+        Self { frames, frame_time }
+    }
+
+    /// Texture standing for the whole animation in definitions and meshes
+    pub fn first(&self) -> TextureIndex {
+        self.frames[0]
+    }
+
+    /// Frame shown `time` after the animation started, looping
+    pub fn frame_at(&self, time: Duration) -> TextureIndex {
+        let frame = time.as_millis() / self.frame_time.as_millis();
+        self.frames[(frame % self.frames.len() as u128) as usize]
+    }
+}
+
+/// Lookup table the shaders animate layers with, one `(u16, u16, u16, u16)`
+/// texel each
+///
+/// The first `layer_count` texels describe each layer as `(frame count, frame
+/// milliseconds, offset of its frame list, 0)`, still layers having a single
+/// frame. Frame lists follow, one `(layer, 0, 0, 0)` texel per frame.
+pub fn animation_table<'a>(
+    animations: impl IntoIterator<Item = &'a Animation>,
+    layer_count: usize,
+) -> Vec<(u16, u16, u16, u16)> {
+    let mut table: Vec<_> = (0..layer_count).map(|_| (1, 1, 0, 0)).collect();
+    for animation in animations {
+        // Left to `packs::validation` to report
+        if animation.first().0 as usize >= layer_count {
+            continue;
+        }
+        let offset = table.len() as u16;
+        let frame_time = animation.frame_time.as_millis().min(u16::MAX as u128) as u16;
+        table[animation.first().0 as usize] =
+            (animation.frames.len() as u16, frame_time, offset, 0);
+        table.extend(animation.frames.iter().map(|frame| (frame.0, 0, 0, 0)));
+    }
+    table
+}
+
+/// Texture a face may show in place of the one in its definition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Variant {
+    pub texture: TextureIndex,
+    pub transform: TextureTransform,
+    /// Odds of this variant relative to the others of the same texture
+    pub weight: u32,
+}
+
+impl Variant {
+    pub fn new(texture: TextureIndex, weight: u32) -> Self {
+        Self {
+            texture,
+            transform: TextureTransform::IDENTITY,
+            weight,
+        }
+    }
+
+    pub fn with_transform(self, transform: TextureTransform) -> Self {
+        Self { transform, ..self }
+    }
+
+    /// `texture` in each of its four quarter turns, equally likely
+    pub fn rotations(texture: TextureIndex) -> Vec<Variant> {
+        (0..4)
+            .map(|turns| Self::new(texture, 1).with_transform(TextureTransform::new(turns, false)))
+            .collect()
+    }
+}
+
+/// Hash of a block position mixing every bit, the same on every run
+fn position_hash(position: WorldPosition) -> u32 {
+    let mut hash = (position.x as u32).wrapping_mul(0x9E37_79B1)
+        ^ (position.y as u32).wrapping_mul(0x85EB_CA77)
+        ^ (position.z as u32).wrapping_mul(0xC2B2_AE3D);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x2C1B_3C6D);
+    hash ^= hash >> 12;
+    hash = hash.wrapping_mul(0x297A_2D39);
+    hash ^ hash >> 15
+}
+
+/// Variant picked for a block at `position`, all faces of a block agreeing
+fn pick_variant(variants: &[Variant], position: WorldPosition) -> Variant {
+    let total: u32 = variants.iter().map(|variant| variant.weight).sum();
+    let mut roll = position_hash(position) % total;
+    for variant in variants {
+        if roll < variant.weight {
+            return *variant;
+        }
+        roll -= variant.weight;
+    }
+    unreachable!("roll is below the total weight")
+}
+
+/// Face textures of a non-empty block, one set per state the block supports,
+/// along with the `BlockKind` deciding how it is meshed
+///
+/// The first set belongs to the default state and is what indexing by
+/// `BlockFace` returns; states past the declared ones fall back to it. Cross
+/// plants take the texture of each of their quads from the side faces. Faces
+/// showing the first frame of one of the block's `Animation`s are animated.
+///
+/// Faces may be turned by a `TextureTransform`, and faces showing a texture
+/// with `Variant`s show one of them instead, picked from the block position
+/// (see `texture_at`). Connected textures are swapped by meshers for the tile
+/// joining the neighbours of the same block (see `with_connected`). Faces with a
+/// `Tint` are coloured by the climate around them, in every state.
+///
+/// The per-state faces live on the heap, so definitions are `Clone` but not
+/// `Copy`.
+#[derive(Debug, Clone)]
+pub struct SolidBlockDefinition {
+    states: Vec<EnumMap<BlockFace, TextureIndex>>,
+    /// Face transforms, one set per state
+    transforms: Vec<EnumMap<BlockFace, TextureTransform>>,
+    kind: BlockKind,
+    animations: Vec<Animation>,
+    variants: Vec<(TextureIndex, Vec<Variant>)>,
+    connected: Vec<(TextureIndex, [TextureIndex; 16])>,
+    tints: EnumMap<BlockFace, Tint>,
+}
 
 impl<'a> IntoIterator for &'a SolidBlockDefinition {
     type Item = (BlockFace, &'a TextureIndex);
@@ -55,7 +316,7 @@ impl<'a> IntoIterator for &'a SolidBlockDefinition {
     type IntoIter = enum_map::Iter<'a, BlockFace, TextureIndex>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+        self.states[0].iter()
     }
 }
 
@@ -63,13 +324,27 @@ impl Index<BlockFace> for SolidBlockDefinition {
     type Output = TextureIndex;
 
     fn index(&self, index: BlockFace) -> &Self::Output {
-        &self.0[index]
+        &self.states[0][index]
+    }
+}
+
+impl From<EnumMap<BlockFace, TextureIndex>> for SolidBlockDefinition {
+    fn from(faces: EnumMap<BlockFace, TextureIndex>) -> Self {
+        Self {
+            states: vec![faces],
+            transforms: vec![EnumMap::default()],
+            kind: BlockKind::Opaque,
+            animations: Vec::new(),
+            variants: Vec::new(),
+            connected: Vec::new(),
+            tints: EnumMap::default(),
+        }
     }
 }
 
 impl<F: FnMut(BlockFace) -> TextureIndex> From<F> for SolidBlockDefinition {
     fn from(f: F) -> Self {
-        Self(EnumMap::from(f))
+        Self::from(EnumMap::from(f))
     }
 }
 
@@ -92,7 +367,7 @@ impl SolidBlockDefinition {
         up: TextureIndex,
         down: TextureIndex,
     ) -> Self {
-        Self(enum_map! {
+        Self::from(enum_map! {
             BlockFace::North => north,
             BlockFace::South => south,
             BlockFace::East => east,
@@ -101,4 +376,333 @@ impl SolidBlockDefinition {
             BlockFace::Down => down,
         })
     }
+
+    /// Block supporting every state of `K`, `f` gives the (default state) faces of each
+    ///
+    /// Animations, variants and connected tiles of every state apply to the
+    /// whole block. So do the kind and tints, which all states have to share.
+    pub fn with_states<K: StateKind>(mut f: impl FnMut(K) -> SolidBlockDefinition) -> Self {
+        let mut block = Self {
+            states: Vec::new(),
+            transforms: Vec::new(),
+            kind: BlockKind::Opaque,
+            animations: Vec::new(),
+            variants: Vec::new(),
+            connected: Vec::new(),
+            tints: EnumMap::default(),
+        };
+        for (index, state) in K::iter().enumerate() {
+            let definition = f(state);
+            if index == 0 {
+                block.kind = definition.kind;
+                block.tints = definition.tints;
+            }
+            assert!(
+                definition.kind == block.kind && definition.tints == block.tints,
+                "states of a block differ in kind or tints"
+            );
+            block.states.push(definition.states[0]);
+            block.transforms.push(definition.transforms[0]);
+            block.animations.extend(definition.animations);
+            block.variants.extend(definition.variants);
+            block.connected.extend(definition.connected);
+        }
+        block
+    }
+
+    /// Block showing `animation` on every face
+    pub fn animated(animation: Animation) -> Self {
+        Self::from(animation.first()).with_animation(animation)
+    }
+
+    /// Animate the faces showing the first frame of `animation`
+    pub fn with_animation(mut self, animation: Animation) -> Self {
+        self.animations.push(animation);
+        self
+    }
+
+    pub fn animations(&self) -> &[Animation] {
+        &self.animations
+    }
+
+    /// Turn the texture of `face` in every state
+    pub fn with_transform(mut self, face: BlockFace, transform: TextureTransform) -> Self {
+        for transforms in &mut self.transforms {
+            transforms[face] = transform;
+        }
+        self
+    }
+
+    /// Show one of `variants` on the faces showing `texture`; the variant
+    /// transform applies before the transform of the face
+    pub fn with_variants(mut self, texture: TextureIndex, variants: Vec<Variant>) -> Self {
+        assert!(
+            variants.iter().any(|variant| variant.weight > 0),
+            "variants need a positive total weight"
+        );
+        self.variants.retain(|(base, _)| *base != texture);
+        self.variants.push((texture, variants));
+        self
+    }
+
+    /// Textures with variants, along with their variants
+    pub fn variants(&self) -> &[(TextureIndex, Vec<Variant>)] {
+        &self.variants
+    }
+
+    /// Join faces showing `texture` with the neighbours of the same block in
+    /// their plane
+    ///
+    /// `tiles[mask]` replaces `texture`, where bit `i` of `mask` is set when the
+    /// neighbour towards `face.plane_sides()[i]` is the same block (in any
+    /// state); `tiles[0]` is usually `texture` itself. Connected tiles are
+    /// picked after variants and keep the transform of the face.
+    pub fn with_connected(mut self, texture: TextureIndex, tiles: [TextureIndex; 16]) -> Self {
+        self.connected.retain(|(base, _)| *base != texture);
+        self.connected.push((texture, tiles));
+        self
+    }
+
+    /// Tiles joining `texture` with its neighbours, if it is connected
+    pub fn connected(&self, texture: TextureIndex) -> Option<&[TextureIndex; 16]> {
+        self.connected
+            .iter()
+            .find(|(base, _)| *base == texture)
+            .map(|(_, tiles)| tiles)
+    }
+
+    /// Colour `face` by `tint`
+    pub fn with_tint(mut self, face: BlockFace, tint: Tint) -> Self {
+        self.tints[face] = tint;
+        self
+    }
+
+    /// Colour every face by `tint`
+    pub fn tinted(self, tint: Tint) -> Self {
+        Self {
+            tints: EnumMap::from(|_| tint),
+            ..self
+        }
+    }
+
+    pub fn tint(&self, face: BlockFace) -> Tint {
+        self.tints[face]
+    }
+
+    pub fn with_kind(self, kind: BlockKind) -> Self {
+        Self { kind, ..self }
+    }
+
+    pub fn kind(&self) -> BlockKind {
+        self.kind
+    }
+
+    /// Number of states this block supports
+    pub fn state_count(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn supports(&self, state: BlockState) -> bool {
+        (state.0 as usize) < self.states.len()
+    }
+
+    /// Face textures for `state`
+    pub fn faces(&self, state: BlockState) -> &EnumMap<BlockFace, TextureIndex> {
+        self.states.get(state.0 as usize).unwrap_or(&self.states[0])
+    }
+
+    /// Texture of `face` for `state`
+    pub fn texture(&self, state: BlockState, face: BlockFace) -> TextureIndex {
+        self.faces(state)[face]
+    }
+
+    /// Transform of `face` for `state`
+    pub fn transform(&self, state: BlockState, face: BlockFace) -> TextureTransform {
+        self.transforms
+            .get(state.0 as usize)
+            .unwrap_or(&self.transforms[0])[face]
+    }
+
+    /// Every texture this block may show: its faces in every state, variants,
+    /// connected tiles and animation frames, possibly repeated
+    pub fn textures(&self) -> impl Iterator<Item = TextureIndex> + '_ {
+        let faces = self.states.iter().flat_map(|faces| faces.values().copied());
+        let variants = self.variants.iter().flat_map(|(base, variants)| {
+            std::iter::once(*base).chain(variants.iter().map(|variant| variant.texture))
+        });
+        let connected = self
+            .connected
+            .iter()
+            .flat_map(|(base, tiles)| std::iter::once(*base).chain(tiles.iter().copied()));
+        let frames = self
+            .animations
+            .iter()
+            .flat_map(|animation| animation.frames.iter().copied());
+        faces.chain(variants).chain(connected).chain(frames)
+    }
+
+    /// The same block with every texture passed through `remap`, e.g. when its
+    /// pack's layers move into a larger texture array
+    pub fn remapped(mut self, remap: impl Fn(TextureIndex) -> TextureIndex) -> Self {
+        for faces in &mut self.states {
+            for texture in faces.values_mut() {
+                *texture = remap(*texture);
+            }
+        }
+        for (base, variants) in &mut self.variants {
+            *base = remap(*base);
+            for variant in variants {
+                variant.texture = remap(variant.texture);
+            }
+        }
+        for (base, tiles) in &mut self.connected {
+            *base = remap(*base);
+            for tile in tiles {
+                *tile = remap(*tile);
+            }
+        }
+        for animation in &mut self.animations {
+            for frame in &mut animation.frames {
+                *frame = remap(*frame);
+            }
+        }
+        self
+    }
+
+    /// Texture and transform `face` shows for `state` on the block at `position`,
+    /// what meshers emit
+    pub fn texture_at(
+        &self,
+        state: BlockState,
+        face: BlockFace,
+        position: WorldPosition,
+    ) -> (TextureIndex, TextureTransform) {
+        let texture = self.texture(state, face);
+        let transform = self.transform(state, face);
+        match self.variants.iter().find(|(base, _)| *base == texture) {
+            Some((_, variants)) => {
+                let variant = pick_variant(variants, position);
+                (variant.texture, variant.transform.then(transform))
+            }
+            None => (texture, transform),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packs::{basic::*, Pack};
+
+    use super::*;
+
+    #[test]
+    fn animations_loop_through_frames() {
+        let frames = vec![TextureIndex(3), TextureIndex(7), TextureIndex(8)];
+        let animation = Animation::new(frames, Duration::from_millis(100));
+        let at = |millis| animation.frame_at(Duration::from_millis(millis));
+        assert_eq!(at(0), TextureIndex(3));
+        assert_eq!(at(199), TextureIndex(7));
+        assert_eq!(at(650), TextureIndex(3));
+        let table = animation_table(&[animation], 9);
+        assert_eq!(table.len(), 12);
+        assert_eq!(table[0], (1, 1, 0, 0));
+        assert_eq!(table[3], (3, 100, 9, 0));
+        assert_eq!(table[10], (7, 0, 0, 0));
+    }
+
+    #[test]
+    fn basic_liquids_animate() {
+        let layers = BasicPack::get_textures().len();
+        for &id in &[BasicId::Water, BasicId::Lava] {
+            let definition = &BasicPack::get_map()[id];
+            let animation = &definition.animations()[0];
+            assert_eq!(definition[BlockFace::Up], animation.first());
+            assert!(animation
+                .frames
+                .iter()
+                .all(|frame| (frame.0 as usize) < layers));
+        }
+    }
+
+    #[test]
+    fn transforms_compose() {
+        let all: Vec<_> = (0..8)
+            .map(|bits| TextureTransform::new(bits & 3, bits & 4 != 0))
+            .collect();
+        for &first in &all {
+            assert_eq!(
+                TextureTransform::new(first.bits() & 3, first.bits() & 4 != 0),
+                first
+            );
+            for &next in &all {
+                for order in 0..4 {
+                    assert_eq!(
+                        first.then(next).apply(order),
+                        next.apply(first.apply(order))
+                    );
+                }
+            }
+        }
+        let quarter = TextureTransform::new(1, false);
+        let infos = TextureIndex(5).into_arr_transformed(3, 1, quarter);
+        let plain = TextureIndex(5).into_arr_repeat(1, 3);
+        let orders: Vec<_> = infos.iter().map(|info| info.order).collect();
+        assert_eq!(orders, [0b10, 0b00, 0b01, 0b11]);
+        assert!(infos.iter().zip(&plain).all(|(a, b)| a.repeat == b.repeat));
+    }
+
+    #[test]
+    fn states_share_kind_and_tints() {
+        let definition = SolidBlockDefinition::with_states(|style: GrassStyle| {
+            SolidBlockDefinition::from(TextureIndex(style as u16))
+                .with_kind(BlockKind::Cross)
+                .with_tint(BlockFace::Up, Tint::Grass)
+        });
+        assert_eq!(definition.state_count(), 4);
+        assert_eq!(definition.kind(), BlockKind::Cross);
+        assert_eq!(definition.tint(BlockFace::Up), Tint::Grass);
+        assert_eq!(definition.tint(BlockFace::Down), Tint::None);
+    }
+
+    #[test]
+    #[should_panic(expected = "differ in kind or tints")]
+    fn states_reject_other_kinds() {
+        SolidBlockDefinition::with_states(|style: GrassStyle| {
+            let definition = SolidBlockDefinition::from(TextureIndex(0));
+            match style {
+                GrassStyle::Tall => definition.with_kind(BlockKind::Cross),
+                _ => definition,
+            }
+        });
+    }
+
+    #[test]
+    fn variants_follow_position() {
+        let (plain, alt) = (TextureIndex(1), TextureIndex(2));
+        let definition = SolidBlockDefinition::from(plain)
+            .with_variants(plain, vec![Variant::new(plain, 3), Variant::new(alt, 1)])
+            .with_transform(BlockFace::Up, TextureTransform::new(2, false));
+        let mut alts = 0;
+        for x in 0..32 {
+            for z in 0..32 {
+                let position = WorldPosition::new(x, 7, z);
+                let (texture, transform) =
+                    definition.texture_at(BlockState::default(), BlockFace::North, position);
+                assert_eq!(transform, TextureTransform::IDENTITY);
+                for face in [BlockFace::South, BlockFace::Up] {
+                    let at = definition.texture_at(BlockState::default(), face, position);
+                    assert_eq!(at.0, texture);
+                }
+                alts += (texture == alt) as usize;
+            }
+        }
+        // A quarter of 1024 blocks, give or take
+        assert!((200..312).contains(&alts), "{} alts", alts);
+        let up = definition.texture_at(
+            BlockState::default(),
+            BlockFace::Up,
+            WorldPosition::new(0, 0, 0),
+        );
+        assert_eq!(up.1.rotation(), 2);
+    }
 }
@@ -1,15 +1,19 @@
-use crate::{BlockSubPos, ChunkPos};
+use std::{convert::TryFrom, fmt::Display};
 
+use crate::{BlockFace, BlockSubPos, ChunkCoord, ChunkPos};
+
+/// Block position in world coordinates, negative along `x` and `z` for chunks
+/// at negative `ChunkCoord`s
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct WorldPosition {
-    pub x: u32,
+    pub x: i32,
     pub y: u16,
-    pub z: u32,
+    pub z: i32,
 }
 
-impl Into<[f32; 3]> for WorldPosition {
-    fn into(self) -> [f32; 3] {
-        [self.x as f32, self.y as f32, self.z as f32]
+impl From<WorldPosition> for [f32; 3] {
+    fn from(pos: WorldPosition) -> Self {
+        [pos.x as f32, pos.y as f32, pos.z as f32]
     }
 }
 
@@ -20,14 +24,103 @@ impl<const SIZE: usize, const WIDTH: usize, const CHUNK_SIZE: usize, const CHUNK
         let (chk_x, chk_z) = chk.into();
         let (blk_x, blk_y, blk_z) = blk.into();
         Self {
-            x: (chk_x as u32) * (CHUNK_WIDTH as u32) + (blk_x as u32),
+            x: (chk_x as i32) * (CHUNK_WIDTH as i32) + (blk_x as i32),
+            y: blk_y,
+            z: (chk_z as i32) * (CHUNK_WIDTH as i32) + (blk_z as i32),
+        }
+    }
+}
+
+impl<const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    From<(ChunkCoord, BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>)> for WorldPosition
+{
+    fn from((chk, blk): (ChunkCoord, BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>)) -> Self {
+        let (blk_x, blk_y, blk_z) = blk.into();
+        Self {
+            x: chk.x * (CHUNK_WIDTH as i32) + (blk_x as i32),
             y: blk_y,
-            z: (chk_z as u32) * (CHUNK_WIDTH as u32) + (blk_z as u32),
+            z: chk.z * (CHUNK_WIDTH as i32) + (blk_z as i32),
         }
     }
 }
 
+/// Error for a `WorldPosition` that lies outside the world it is used with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfWorld(pub WorldPosition);
+
+impl Display for OutOfWorld {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let WorldPosition { x, y, z } = self.0;
+        write!(f, "position ({}, {}, {}) is outside the world", x, y, z)
+    }
+}
+
+impl std::error::Error for OutOfWorld {}
+
+impl<const SIZE: usize, const WIDTH: usize, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>
+    TryFrom<WorldPosition> for (ChunkPos<SIZE, WIDTH>, BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>)
+{
+    type Error = OutOfWorld;
+
+    fn try_from(pos: WorldPosition) -> Result<Self, Self::Error> {
+        let (coord, block_pos) = pos.chunk_coord().ok_or(OutOfWorld(pos))?;
+        ChunkPos::from_coord(coord)
+            .map(|chunk_pos| (chunk_pos, block_pos))
+            .ok_or(OutOfWorld(pos))
+    }
+}
+
 impl WorldPosition {
+    pub fn new(x: i32, y: u16, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Coordinate of the chunk holding this block and its position in that
+    /// chunk, or `None` when it lies above the chunk height
+    pub fn chunk_coord<const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
+        self,
+    ) -> Option<(ChunkCoord, BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>)> {
+        let width = CHUNK_WIDTH as i32;
+        let block_pos = BlockSubPos::try_new(
+            self.x.rem_euclid(width) as u16,
+            self.y,
+            self.z.rem_euclid(width) as u16,
+        )?;
+        let coord = ChunkCoord::new(self.x.div_euclid(width), self.z.div_euclid(width));
+        Some((coord, block_pos))
+    }
+
+    /// Chunk and chunk-local position of this block in a
+    /// `World<_, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>`
+    pub fn split<
+        const SIZE: usize,
+        const WIDTH: usize,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+    >(
+        self,
+    ) -> Result<(ChunkPos<SIZE, WIDTH>, BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>), OutOfWorld> {
+        TryFrom::try_from(self)
+    }
+
+    /// Position of the block next to this one in the direction of `face`,
+    /// or `None` when that would leave the coordinate range
+    pub fn step(self, face: BlockFace) -> Option<Self> {
+        let (dx, dy, dz) = face.offset();
+        Some(Self {
+            x: self.x.checked_add(dx)?,
+            y: self.y.checked_add_signed(dy as i16)?,
+            z: self.z.checked_add(dz)?,
+        })
+    }
+
+    pub fn shift(self, x: i32, y: u16, z: i32) -> Self {
+        Self {
+            x: self.x + x,
+            y: self.y + y,
+            z: self.z + z,
+        }
+    }
     pub fn ix(self) -> Self {
         Self {
             x: self.x + 1,
@@ -1,7 +1,14 @@
-use std::{alloc::{alloc_zeroed, Layout}, fmt::Display, marker::PhantomData, ops::{Index, IndexMut}};
+use std::{alloc::{alloc, handle_alloc_error, Layout}, convert::TryFrom, fmt::Display, marker::PhantomData, ops::{Index, IndexMut}};
 
-use crate::{BlockId, Chunk};
+use crate::{
+    chunk::fnv, Block, BlockFace, BlockId, BlockSubPos, Chunk, ChunkStorage, OutOfWorld,
+    WorldPosition,
+};
 
+/// Fixed grid of `SIZE` chunks, `WIDTH` chunks along x, each stored as `S`
+///
+/// Every way to modify a chunk (`set`, `chunk_mut`, `IndexMut` or mutable
+/// iteration, which generators use) flags it dirty until `take_dirty` is called.
 #[derive(Debug, Clone, Copy)]
 pub struct World<
     Id: BlockId,
@@ -9,7 +16,13 @@ pub struct World<
     const WIDTH: usize,
     const CHUNK_SIZE: usize,
     const CHUNK_WIDTH: usize,
->(pub [Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>; SIZE]);
+    S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> = Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+> {
+    chunks: [S; SIZE],
+    phat: PhantomData<Id>,
+    /// Chunks modified since the last `take_dirty`
+    dirty: [bool; SIZE],
+}
 
 impl<
         Id: BlockId,
@@ -17,7 +30,8 @@ impl<
         const WIDTH: usize,
         const CHUNK_SIZE: usize,
         const CHUNK_WIDTH: usize,
-    > Display for World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> + Display,
+    > Display for World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(
@@ -42,16 +56,100 @@ impl<
         const WIDTH: usize,
         const CHUNK_SIZE: usize,
         const CHUNK_WIDTH: usize,
-    > World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>
 {
     pub const LENGTH: usize = SIZE / WIDTH;
 
     pub fn create() -> Box<Self> {
-        let layout = Layout::new::<World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>>();
+        let layout = Layout::new::<World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>>();
+        // All-zero bytes are not necessarily `Block::Empty` (the enum may use a niche),
+        // so every chunk is written explicitly
         unsafe {
-            let pointer = alloc_zeroed(layout);
-            Box::from_raw(pointer as *mut World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>)
+            let pointer = alloc(layout) as *mut World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>;
+            if pointer.is_null() {
+                handle_alloc_error(layout);
+            }
+            let chunks = std::ptr::addr_of_mut!((*pointer).chunks) as *mut S;
+            for i in 0..SIZE {
+                chunks.add(i).write(Default::default());
+            }
+            std::ptr::addr_of_mut!((*pointer).dirty).write([true; SIZE]);
+            Box::from_raw(pointer)
+        }
+    }
+
+    /// Block at `pos`, or `None` outside the world
+    pub fn get(&self, pos: WorldPosition) -> Option<Block<Id>> {
+        let (chunk_pos, block_pos) = pos.split::<SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>().ok()?;
+        Some(self[chunk_pos].get(block_pos))
+    }
+
+    /// Block next to `block_pos` in the direction of `face`, see `ChunkSource::neighbour`
+    pub fn neighbour(
+        &self,
+        chunk_pos: ChunkPos<SIZE, WIDTH>,
+        block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
+        face: BlockFace,
+    ) -> Option<Block<Id>> {
+        ChunkSource::neighbour(self, chunk_pos.into(), block_pos, face)
+    }
+
+    /// Block next to `pos` in the direction of `face`, or `None` outside the world
+    pub fn neighbour_at(&self, pos: WorldPosition, face: BlockFace) -> Option<Block<Id>> {
+        self.get(pos.step(face)?)
+    }
+
+    /// Replace the block at `pos`, failing outside the world
+    pub fn set(&mut self, pos: WorldPosition, block: Block<Id>) -> Result<(), OutOfWorld> {
+        let (chunk_pos, block_pos) = pos.split::<SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>()?;
+        self.chunks[chunk_pos.as_index()].set(block_pos, block);
+        for coord in affected_chunks(chunk_pos.into(), block_pos) {
+            self.flag_dirty(coord);
         }
+        Ok(())
+    }
+
+    /// Flag `chunk_pos` as modified, see `ChunkCoord::with_neighbours`
+    pub fn mark_dirty(&mut self, chunk_pos: ChunkPos<SIZE, WIDTH>) {
+        for coord in ChunkCoord::from(chunk_pos).with_neighbours() {
+            self.flag_dirty(coord);
+        }
+    }
+
+    fn flag_dirty(&mut self, coord: ChunkCoord) {
+        if let Some(chunk_pos) = ChunkPos::<SIZE, WIDTH>::from_coord(coord) {
+            self.dirty[chunk_pos.as_index()] = true;
+        }
+    }
+
+    pub fn is_dirty(&self, chunk_pos: ChunkPos<SIZE, WIDTH>) -> bool {
+        self.dirty[chunk_pos.as_index()]
+    }
+
+    /// Chunk at `chunk_pos`, marked dirty (see `mark_dirty`)
+    pub fn chunk_mut(&mut self, chunk_pos: ChunkPos<SIZE, WIDTH>) -> &mut S {
+        self.mark_dirty(chunk_pos);
+        &mut self.chunks[chunk_pos.as_index()]
+    }
+
+    /// Every chunk, in `ChunkPos` index order
+    pub fn chunks(&self) -> &[S; SIZE] {
+        &self.chunks
+    }
+
+    /// Hash of every block, see `world_hash`
+    pub fn content_hash(&self) -> u64 {
+        world_hash(self.into_iter().map(|(pos, chunk)| (pos.into(), chunk)))
+    }
+
+    /// Chunks modified since the previous call, clearing their flags
+    pub fn take_dirty(&mut self) -> Vec<ChunkPos<SIZE, WIDTH>> {
+        let dirty = ChunkPosIterator::default()
+            .filter(|pos: &ChunkPos<SIZE, WIDTH>| self.dirty[pos.as_index()])
+            .collect();
+        self.dirty = [false; SIZE];
+        dirty
     }
 }
 
@@ -92,9 +190,9 @@ impl<const SIZE: usize, const WIDTH: usize> Display for ChunkPos<SIZE, WIDTH> {
     }
 }
 
-impl<const SIZE: usize, const WIDTH: usize> Into<(u16, u16)> for ChunkPos<SIZE, WIDTH> {
-    fn into(self) -> (u16, u16) {
-        let input = self.0;
+impl<const SIZE: usize, const WIDTH: usize> From<ChunkPos<SIZE, WIDTH>> for (u16, u16) {
+    fn from(pos: ChunkPos<SIZE, WIDTH>) -> Self {
+        let input = pos.0;
         let x = (input % WIDTH) as u16;
         let z = (input / WIDTH) as u16;
         (x, z)
@@ -108,6 +206,22 @@ impl<const SIZE: usize, const WIDTH: usize> ChunkPos<SIZE, WIDTH> {
         Self((x as usize) + (z as usize) * WIDTH)
     }
 
+    /// Like `new`, but returns `None` for coordinates outside the world
+    pub fn try_new(x: u16, z: u16) -> Option<Self> {
+        if (x as usize) < WIDTH && (z as usize) < SIZE / WIDTH {
+            Some(Self::new(x, z))
+        } else {
+            None
+        }
+    }
+
+    /// Position of the chunk at `coord`, or `None` outside the world
+    pub fn from_coord(coord: ChunkCoord) -> Option<Self> {
+        let x = u16::try_from(coord.x).ok()?;
+        let z = u16::try_from(coord.z).ok()?;
+        Self::try_new(x, z)
+    }
+
     pub fn as_index(self) -> usize {
         self.0
     }
@@ -116,6 +230,153 @@ impl<const SIZE: usize, const WIDTH: usize> ChunkPos<SIZE, WIDTH> {
         assert!(input < SIZE);
         Self(input)
     }
+
+    /// Chunk next to this one in the direction of a horizontal `face`, or `None`
+    /// past the world edge
+    pub fn step(self, face: BlockFace) -> Option<Self> {
+        Self::from_coord(ChunkCoord::from(self).step(face))
+    }
+}
+
+/// Signed chunk coordinate, independent of any world's bounds
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChunkCoord {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkCoord {
+    pub fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// Chunk next to this one in the direction of a horizontal `face`
+    pub fn step(self, face: BlockFace) -> Self {
+        let (dx, _, dz) = face.offset();
+        Self::new(self.x + dx, self.z + dz)
+    }
+
+    /// This chunk and its horizontal neighbours, whose faces may depend on its
+    /// edge blocks: the chunks to remesh when it changes
+    pub fn with_neighbours(self) -> impl Iterator<Item = ChunkCoord> {
+        let faces = [
+            BlockFace::North,
+            BlockFace::South,
+            BlockFace::East,
+            BlockFace::West,
+        ];
+        std::iter::once(self).chain(IntoIterator::into_iter(faces).map(move |face| self.step(face)))
+    }
+}
+
+/// Chunks to remesh when the block at `block_pos` of the chunk at `coord`
+/// changes: that chunk, and the neighbours it touches when the block lies on a
+/// chunk edge (only those blocks can change the faces of the next chunk)
+pub fn affected_chunks<const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize>(
+    coord: ChunkCoord,
+    block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
+) -> impl Iterator<Item = ChunkCoord> {
+    let (x, _, z) = block_pos.into();
+    let last = CHUNK_WIDTH as u16 - 1;
+    let edges = [
+        (x == 0, BlockFace::West),
+        (x == last, BlockFace::East),
+        (z == 0, BlockFace::North),
+        (z == last, BlockFace::South),
+    ];
+    std::iter::once(coord).chain(
+        IntoIterator::into_iter(edges)
+            .filter(|&(edge, _)| edge)
+            .map(move |(_, face)| coord.step(face)),
+    )
+}
+
+impl Display for ChunkCoord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.z)
+    }
+}
+
+impl<const SIZE: usize, const WIDTH: usize> From<ChunkPos<SIZE, WIDTH>> for ChunkCoord {
+    fn from(pos: ChunkPos<SIZE, WIDTH>) -> Self {
+        let (x, z) = pos.into();
+        Self::new(x as i32, z as i32)
+    }
+}
+
+/// Hash of the blocks of `chunks` and where they lie, to check that a seeded
+/// generator reproduces a world
+///
+/// Chunks may come in any order, so every world layout (`World`, `DynamicWorld`,
+/// `InfiniteWorld`) holding the same chunks hashes the same; the hash is stable
+/// across runs and machines, see `ChunkStorage::content_hash`.
+pub fn world_hash<
+    'a,
+    Id: BlockId,
+    const CHUNK_SIZE: usize,
+    const CHUNK_WIDTH: usize,
+    S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH> + 'a,
+>(
+    chunks: impl IntoIterator<Item = (ChunkCoord, &'a S)>,
+) -> u64 {
+    chunks
+        .into_iter()
+        .map(|(coord, chunk)| {
+            let hash = fnv(chunk.content_hash(), &coord.x.to_le_bytes());
+            let hash = fnv(hash, &coord.z.to_le_bytes());
+            // Spread the bits before summing, so chunks do not cancel out
+            (hash ^ hash >> 31).wrapping_mul(0x7FB5_D329_728E_A185)
+        })
+        .fold(0, u64::wrapping_add)
+}
+
+/// Chunk lookup shared by every world layout, used by meshers to look across chunk edges
+pub trait ChunkSource<Id: BlockId, const CHUNK_SIZE: usize, const CHUNK_WIDTH: usize> {
+    type Storage: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>;
+
+    /// Chunk at `coord`, or `None` when it lies outside the world
+    fn chunk(&self, coord: ChunkCoord) -> Option<&Self::Storage>;
+
+    /// Block next to `block_pos` in the direction of `face`, stepping into the
+    /// adjacent chunk when needed; `None` when that block lies outside the world
+    /// (past a missing chunk, or above/below the chunk height)
+    fn neighbour(
+        &self,
+        coord: ChunkCoord,
+        block_pos: BlockSubPos<CHUNK_SIZE, CHUNK_WIDTH>,
+        face: BlockFace,
+    ) -> Option<Block<Id>> {
+        let (dx, dy, dz) = face.offset();
+        let (blk_x, blk_y, blk_z) = block_pos.into();
+        let width = CHUNK_WIDTH as i32;
+        let y = u16::try_from(blk_y as i32 + dy).ok()?;
+        let x = blk_x as i32 + dx;
+        let z = blk_z as i32 + dz;
+        let chunk = self.chunk(ChunkCoord::new(
+            coord.x + x.div_euclid(width),
+            coord.z + z.div_euclid(width),
+        ))?;
+        let block_pos =
+            BlockSubPos::try_new(x.rem_euclid(width) as u16, y, z.rem_euclid(width) as u16)?;
+        Some(chunk.get(block_pos))
+    }
+}
+
+impl<
+        Id: BlockId,
+        const SIZE: usize,
+        const WIDTH: usize,
+        const CHUNK_SIZE: usize,
+        const CHUNK_WIDTH: usize,
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > ChunkSource<Id, CHUNK_SIZE, CHUNK_WIDTH>
+    for World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>
+{
+    type Storage = S;
+
+    fn chunk(&self, coord: ChunkCoord) -> Option<&S> {
+        ChunkPos::from_coord(coord).map(|chunk_pos| &self[chunk_pos])
+    }
 }
 
 impl<
@@ -124,12 +385,13 @@ impl<
         const WIDTH: usize,
         const CHUNK_SIZE: usize,
         const CHUNK_WIDTH: usize,
-    > Index<ChunkPos<SIZE, WIDTH>> for World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > Index<ChunkPos<SIZE, WIDTH>> for World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>
 {
-    type Output = Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>;
+    type Output = S;
 
     fn index(&self, index: ChunkPos<SIZE, WIDTH>) -> &Self::Output {
-        &self.0[index.as_index()]
+        &self.chunks[index.as_index()]
     }
 }
 
@@ -139,10 +401,11 @@ impl<
         const WIDTH: usize,
         const CHUNK_SIZE: usize,
         const CHUNK_WIDTH: usize,
-    > IndexMut<ChunkPos<SIZE, WIDTH>> for World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IndexMut<ChunkPos<SIZE, WIDTH>> for World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>
 {
     fn index_mut(&mut self, index: ChunkPos<SIZE, WIDTH>) -> &mut Self::Output {
-        &mut self.0[index.as_index()]
+        self.chunk_mut(index)
     }
 }
 
@@ -153,20 +416,15 @@ impl<
         const WIDTH: usize,
         const CHUNK_SIZE: usize,
         const CHUNK_WIDTH: usize,
-    > IntoIterator for &'world World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IntoIterator for &'world World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>
 {
-    type Item = (
-        ChunkPos<SIZE, WIDTH>,
-        &'world Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>,
-    );
+    type Item = (ChunkPos<SIZE, WIDTH>, &'world S);
 
-    type IntoIter = std::iter::Zip<
-        ChunkPosIterator<SIZE, WIDTH>,
-        std::slice::Iter<'world, Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>>,
-    >;
+    type IntoIter = std::iter::Zip<ChunkPosIterator<SIZE, WIDTH>, std::slice::Iter<'world, S>>;
 
     fn into_iter(self) -> Self::IntoIter {
-        ChunkPosIterator::default().zip(self.0.iter())
+        ChunkPosIterator::default().zip(self.chunks.iter())
     }
 }
 
@@ -177,19 +435,183 @@ impl<
         const WIDTH: usize,
         const CHUNK_SIZE: usize,
         const CHUNK_WIDTH: usize,
-    > IntoIterator for &'world mut World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH>
+        S: ChunkStorage<Id, CHUNK_SIZE, CHUNK_WIDTH>,
+    > IntoIterator for &'world mut World<Id, SIZE, WIDTH, CHUNK_SIZE, CHUNK_WIDTH, S>
 {
-    type Item = (
-        ChunkPos<SIZE, WIDTH>,
-        &'world mut Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>,
-    );
+    type Item = (ChunkPos<SIZE, WIDTH>, &'world mut S);
 
-    type IntoIter = std::iter::Zip<
-        ChunkPosIterator<SIZE, WIDTH>,
-        std::slice::IterMut<'world, Chunk<Id, CHUNK_SIZE, CHUNK_WIDTH>>,
-    >;
+    type IntoIter = std::iter::Zip<ChunkPosIterator<SIZE, WIDTH>, std::slice::IterMut<'world, S>>;
 
+    /// Flags every chunk dirty, whether or not it is written through the iterator
     fn into_iter(self) -> Self::IntoIter {
-        ChunkPosIterator::default().zip(self.0.iter_mut())
+        self.dirty = [true; SIZE];
+        ChunkPosIterator::default().zip(self.chunks.iter_mut())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        generator::{
+            random::{RandomFill, RandomGenerator},
+            ChunkGenerator, WorldGenerator,
+        },
+        packs::basic::BasicId,
+        BlockSubPos, InfiniteWorld,
+    };
+
+    use super::*;
+
+    type TestWorld = World<BasicId, 4, 2, 4096, 16>;
+
+    #[test]
+    fn splits_world_position() {
+        let chunk_pos = ChunkPos::<4, 2>::new(1, 1);
+        let block_pos = BlockSubPos::<4096, 16>::new(3, 15, 7);
+        let pos: WorldPosition = (chunk_pos, block_pos).into();
+        assert_eq!(pos, WorldPosition::new(19, 15, 23));
+        assert_eq!(pos.split(), Ok((chunk_pos, block_pos)));
+        for &outside in &[
+            WorldPosition::new(32, 0, 0),
+            WorldPosition::new(0, 16, 0),
+            WorldPosition::new(0, 0, 32),
+            WorldPosition::new(-1, 0, 0),
+        ] {
+            assert_eq!(outside.split::<4, 2, 4096, 16>(), Err(OutOfWorld(outside)));
+        }
+        let below = WorldPosition::new(-1, 3, -17);
+        assert_eq!(
+            below.chunk_coord(),
+            Some((
+                ChunkCoord::new(-1, -2),
+                BlockSubPos::<4096, 16>::new(15, 3, 15)
+            ))
+        );
+        let coord = ChunkCoord::new(-1, -2);
+        assert_eq!(
+            WorldPosition::from((coord, BlockSubPos::<4096, 16>::new(15, 3, 15))),
+            below
+        );
+    }
+
+    #[test]
+    fn neighbours_cross_chunks() {
+        let mut world = TestWorld::create();
+        let stone = Block::solid(BasicId::Stone);
+        let sand = Block::solid(BasicId::Sand);
+        world[ChunkPos::new(0, 0)][BlockSubPos::new(15, 0, 0)] = stone;
+        world[ChunkPos::new(1, 0)][BlockSubPos::new(0, 0, 0)] = sand;
+        let left = (ChunkPos::new(0, 0), BlockSubPos::new(15, 0, 0));
+        let right = (ChunkPos::new(1, 0), BlockSubPos::new(0, 0, 0));
+        assert_eq!(world.neighbour(left.0, left.1, BlockFace::East), Some(sand));
+        assert_eq!(
+            world.neighbour(right.0, right.1, BlockFace::West),
+            Some(stone)
+        );
+        assert_eq!(
+            world.neighbour(left.0, left.1, BlockFace::Up),
+            Some(Block::Empty)
+        );
+        assert_eq!(world.neighbour(left.0, left.1, BlockFace::Down), None);
+        assert_eq!(world.neighbour(left.0, left.1, BlockFace::North), None);
+        assert_eq!(
+            world.neighbour(right.0, right.1, BlockFace::East),
+            Some(Block::Empty)
+        );
+        let top = BlockSubPos::new(0, 15, 15);
+        assert_eq!(
+            world.neighbour(ChunkPos::new(1, 1), top, BlockFace::Up),
+            None
+        );
+        assert_eq!(
+            world.neighbour(ChunkPos::new(1, 1), top, BlockFace::South),
+            None
+        );
+
+        let pos: WorldPosition = left.into();
+        assert_eq!(world.neighbour_at(pos, BlockFace::East), Some(sand));
+        assert_eq!(world.neighbour_at(pos, BlockFace::Down), None);
+        assert_eq!(
+            world.neighbour_at(WorldPosition::new(31, 0, 0), BlockFace::East),
+            None
+        );
+    }
+
+    #[test]
+    fn tracks_dirty_chunks() {
+        let mut world = TestWorld::create();
+        assert_eq!(world.take_dirty().len(), 4);
+        assert!(world.take_dirty().is_empty());
+        let stone = Block::solid(BasicId::Stone);
+        world.set(WorldPosition::new(5, 0, 5), stone).unwrap();
+        assert_eq!(world.take_dirty(), vec![ChunkPos::new(0, 0)]);
+        world.set(WorldPosition::new(15, 0, 16), stone).unwrap();
+        assert_eq!(
+            world.take_dirty(),
+            vec![
+                ChunkPos::new(0, 0),
+                ChunkPos::new(0, 1),
+                ChunkPos::new(1, 1)
+            ]
+        );
+        world[ChunkPos::new(1, 0)][BlockSubPos::new(0, 0, 0)] = stone;
+        assert_eq!(world.take_dirty().len(), 3);
+        assert!(!world.is_dirty(ChunkPos::new(1, 0)));
+        world
+            .chunk_mut(ChunkPos::new(0, 1))
+            .set(BlockSubPos::new(0, 0, 0), stone);
+        assert_eq!(world.take_dirty().len(), 3);
+        // Generating rewrites every chunk
+        RandomGenerator::new(RandomFill::Fill, 0).generate(world.as_mut());
+        assert_eq!(world.take_dirty().len(), 4);
+    }
+
+    #[test]
+    fn get_and_set_blocks() {
+        let mut world = TestWorld::create();
+        let pos = WorldPosition::new(17, 2, 30);
+        let stone = Block::solid(BasicId::Stone);
+        assert_eq!(world.get(pos), Some(Block::Empty));
+        assert_eq!(world.set(pos, stone), Ok(()));
+        assert_eq!(world.get(pos), Some(stone));
+        assert_eq!(
+            world[ChunkPos::new(1, 1)][BlockSubPos::new(1, 2, 14)],
+            stone
+        );
+        let outside = WorldPosition::new(17, 2, 32);
+        assert_eq!(world.get(outside), None);
+        assert_eq!(world.set(outside, stone), Err(OutOfWorld(outside)));
+    }
+
+    #[test]
+    fn seeded_worlds_reproduce() {
+        let generator = RandomGenerator::new(RandomFill::FillRate(0.5), 7);
+        let mut world = TestWorld::create();
+        generator.generate(world.as_mut());
+        let mut again = TestWorld::create();
+        generator.generate(again.as_mut());
+        assert_eq!(world.content_hash(), again.content_hash());
+
+        // Chunks do not depend on the order or layout they are generated in
+        let mut infinite = InfiniteWorld::<BasicId, 4096, 16>::new();
+        for &(x, z) in &[(1, 1), (0, 1), (1, 0), (0, 0)] {
+            infinite.get_or_generate(ChunkCoord::new(x, z), &generator);
+        }
+        assert_eq!(world_hash(&infinite), world.content_hash());
+
+        let mut other = TestWorld::create();
+        RandomGenerator::new(RandomFill::FillRate(0.5), 8).generate(other.as_mut());
+        assert_ne!(world.content_hash(), other.content_hash());
+        let mut chunk = Chunk::default();
+        let generate = ChunkGenerator::<BasicId, 4096, 16>::generate_chunk;
+        generate(&generator, ChunkCoord::new(1, 0), &mut chunk);
+        assert_eq!(
+            chunk.content_hash(),
+            world[ChunkPos::new(1, 0)].content_hash()
+        );
+        assert_ne!(
+            chunk.content_hash(),
+            world[ChunkPos::new(0, 1)].content_hash()
+        );
     }
 }